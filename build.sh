@@ -3,22 +3,69 @@ set -e
 
 target=wasm32-wasip1
 
-if [ $# -ne 1 ]; then
-  echo "Error: Expected exactly 1 argument, got $#"
-  echo "Usage: $0 std | wasi"
+if [ $# -eq 0 ]; then
+  echo "Error: Expected at least 1 argument"
+  echo "Usage: $0 (std | wasi | component | reactor | tiny)..."
   exit 1
 fi
 
-bin_name=$1
+# The optimized wasi module measured 59653 bytes with wasm-opt version 116.
+# Raise this deliberately, not casually: the point of that binary is to stay small
+wasi_size_budget=65536 # 64 KiB
 
-case "$bin_name" in
-  "wasi"|"std")
-    echo "Build $bin_name -> ./target/${target}/release/${bin_name}.opt.wasm"
-    RUSTFLAGS="-C link-arg=-s" cargo build --bin $bin_name --release --target $target
-    wasm-opt ./target/$target/release/${bin_name}.wasm --strip-debug --strip-dwarf --enable-bulk-memory -O4 -o ./target/$target/release/${bin_name}.opt.wasm
-    ;;
-  *)
-    echo "Invalid build option '$bin_name'"
-    echo "Expected: std or wasi"
-    ;;
-esac
+report_size() {
+  echo "$1 is $(wc -c < "$1" | tr -d ' ') bytes"
+}
+
+check_size_budget() {
+  size=$(wc -c < "$1" | tr -d ' ')
+  if [ "$size" -gt "$2" ]; then
+    echo "Error: $1 is $size bytes, over the budget of $2 bytes"
+    exit 1
+  fi
+}
+
+for bin_name in "$@"; do
+  case "$bin_name" in
+    "std")
+      echo "Build std -> ./target/${target}/release/std.opt.wasm"
+      RUSTFLAGS="-C link-arg=-s" cargo build --bin std --release --target $target
+      wasm-opt ./target/$target/release/std.wasm --strip-debug --strip-dwarf --enable-bulk-memory -O4 -o ./target/$target/release/std.opt.wasm
+      report_size ./target/$target/release/std.opt.wasm
+      ;;
+    "wasi")
+      # Built with the size profile, and without the JSON, CSV and --stats output of the wasi-report feature
+      echo "Build wasi -> ./target/${target}/small/wasi.opt.wasm"
+      RUSTFLAGS="-C link-arg=-s" cargo build --bin wasi --profile small --target $target --no-default-features --features std
+      wasm-opt ./target/$target/small/wasi.wasm --strip-debug --strip-dwarf --enable-bulk-memory -O4 -o ./target/$target/small/wasi.opt.wasm
+      report_size ./target/$target/small/wasi.opt.wasm
+      check_size_budget ./target/$target/small/wasi.opt.wasm $wasi_size_budget
+      ;;
+    "component")
+      # A Preview 2 component is linked by wasm-component-ld, and wasm-opt only understands core modules
+      echo "Build component -> ./target/wasm32-wasip2/release/sha256_component.wasm"
+      cargo build -p sha256-component --release --target wasm32-wasip2
+      report_size ./target/wasm32-wasip2/release/sha256_component.wasm
+      ;;
+    "reactor")
+      # A reactor for JavaScript hosts imports nothing, so it is built without WASI
+      echo "Build reactor -> ./target/wasm32-unknown-unknown/release/sha256_reactor.opt.wasm"
+      RUSTFLAGS="-C link-arg=-s" cargo build -p sha256-reactor --release --target wasm32-unknown-unknown
+      wasm-opt ./target/wasm32-unknown-unknown/release/sha256_reactor.wasm --strip-debug --strip-dwarf --enable-bulk-memory -O4 -o ./target/wasm32-unknown-unknown/release/sha256_reactor.opt.wasm
+      report_size ./target/wasm32-unknown-unknown/release/sha256_reactor.opt.wasm
+      ;;
+    "tiny")
+      # The allocator-free WASI command brings its own WASI imports, so it links neither std nor wasi-libc.
+      # It is a workspace of its own, but shares the target directory
+      echo "Build tiny -> ./target/wasm32-unknown-unknown/release/sha256_tiny.opt.wasm"
+      RUSTFLAGS="-C link-arg=-s" cargo build --manifest-path tiny/Cargo.toml --target-dir target --release --target wasm32-unknown-unknown
+      wasm-opt ./target/wasm32-unknown-unknown/release/sha256_tiny.wasm --strip-debug --strip-dwarf --enable-bulk-memory -Oz -o ./target/wasm32-unknown-unknown/release/sha256_tiny.opt.wasm
+      report_size ./target/wasm32-unknown-unknown/release/sha256_tiny.opt.wasm
+      ;;
+    *)
+      echo "Invalid build option '$bin_name'"
+      echo "Expected: std, wasi, component, reactor or tiny"
+      exit 1
+      ;;
+  esac
+done
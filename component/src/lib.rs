@@ -0,0 +1,179 @@
+use sha256::{
+    encoding::{Encoding, MAX_ENCODED_LEN},
+    manifest,
+    sha256::*,
+    wasi::{Fd, Preopens},
+};
+use std::{cell::RefCell, io::Write};
+use wasi::{
+    cli::{environment, stderr, stdin, stdout},
+    filesystem::{
+        preopens,
+        types::{Descriptor, DescriptorFlags, OpenFlags, PathFlags},
+    },
+    io::streams::{InputStream, StreamError},
+};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// WASI Preview 2 component
+//
+// The component exports the `sha256:hasher/hashing` interface defined in `wit/hasher.wit` so that other components can
+// compose with it, and `wasi:cli/run` so that it can also be run as a command.  The WASI imports are taken from the
+// `wasi` crate rather than generated again, so a stream passed to `hash-stream` is the `wasi` crate's `InputStream`
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+wit_bindgen::generate!({
+    world: "component",
+    path: "wit",
+    with: {
+        "wasi:cli/environment@0.2.12": wasi::cli::environment,
+        "wasi:cli/stdin@0.2.12": wasi::cli::stdin,
+        "wasi:cli/stdout@0.2.12": wasi::cli::stdout,
+        "wasi:cli/stderr@0.2.12": wasi::cli::stderr,
+        "wasi:clocks/wall-clock@0.2.12": wasi::clocks::wall_clock,
+        "wasi:filesystem/types@0.2.12": wasi::filesystem::types,
+        "wasi:filesystem/preopens@0.2.12": wasi::filesystem::preopens,
+        "wasi:io/error@0.2.12": wasi::io::error,
+        "wasi:io/poll@0.2.12": wasi::io::poll,
+        "wasi:io/streams@0.2.12": wasi::io::streams,
+    },
+});
+
+use exports::sha256::hasher::hashing::{self, GuestHasher};
+use exports::wasi::cli::run;
+
+const CHUNK_SIZE: u64 = 64 * 1024;
+const STDIN_NAME: &str = "-";
+
+struct Component;
+
+// Export names such as `sha256:hasher/hashing@1.0.0#hash-stream` are only valid in WebAssembly, so a native build
+// checks the bindings without exporting them
+#[cfg(target_arch = "wasm32")]
+export!(Component);
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Resource methods only borrow the resource, so the hasher state sits in a `RefCell`
+struct Hasher(RefCell<Sha256>);
+
+impl GuestHasher for Hasher {
+    fn new() -> Hasher {
+        Hasher(RefCell::new(Sha256::new()))
+    }
+
+    fn update(&self, data: Vec<u8>) {
+        self.0.borrow_mut().update(&data);
+    }
+
+    fn finalize(&self) -> Vec<u8> {
+        self.0.take().finalize().as_bytes().to_vec()
+    }
+}
+
+impl hashing::Guest for Component {
+    type Hasher = Hasher;
+
+    fn hash_stream(input: InputStream) -> Result<Vec<u8>, StreamError> {
+        hash_stream(&input).map(|digest| digest.as_bytes().to_vec())
+    }
+}
+
+/// Hash everything that can be read from the stream, which the host reports as `Closed` at the end
+fn hash_stream(input: &InputStream) -> Result<Digest, StreamError> {
+    let mut hasher = Sha256::new();
+
+    loop {
+        match input.blocking_read(CHUNK_SIZE) {
+            Ok(chunk) => hasher.update(&chunk),
+            Err(StreamError::Closed) => return Ok(hasher.finalize()),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Write `<digest>  <path>` for each file named on the command line, or `-` for stdin
+impl run::Guest for Component {
+    fn run() -> Result<(), ()> {
+        let args = environment::get_arguments();
+        let mut stdout = stdout::get_stdout();
+        let mut stderr = stderr::get_stderr();
+
+        if args.len() < 2 {
+            let _ = stderr.write_all(b"Usage: sha256 <filename>...\n");
+            return Err(());
+        }
+
+        // Paths are resolved by `Preopens`, which only needs a handle to tell the directories apart, so each directory
+        // is numbered by its position in the list
+        let dirs = preopens::get_directories();
+        let mut preopens = Preopens::default();
+
+        for (index, (_, name)) in dirs.iter().enumerate() {
+            preopens.push(Fd(index as u32), name.as_bytes());
+        }
+
+        let mut out: Vec<u8> = Vec::new();
+        let mut hash_buf = [0u8; MAX_ENCODED_LEN];
+        let mut all_ok = true;
+
+        for path in &args[1..] {
+            match hash_path(&dirs, &preopens, path) {
+                Ok(digest) => {
+                    let len = Encoding::Hex.encode_into(&digest, &mut hash_buf);
+                    manifest::write_entry(&mut out, &hash_buf[..len], path.as_bytes(), false);
+                }
+                Err(msg) => {
+                    let _ = writeln!(stderr, "sha256: {path}: {msg}");
+                    all_ok = false;
+                }
+            }
+
+            if stdout
+                .write_all(&out)
+                .and_then(|_| Write::flush(&mut stdout))
+                .is_err()
+            {
+                return Err(());
+            }
+
+            out.clear();
+        }
+
+        if all_ok { Ok(()) } else { Err(()) }
+    }
+}
+
+/// Open a path relative to the preopened directory containing it and hash its contents
+fn hash_path(
+    dirs: &[(Descriptor, String)],
+    preopens: &Preopens,
+    path: &str,
+) -> Result<Digest, String> {
+    if path == STDIN_NAME {
+        return hash_stream(&stdin::get_stdin()).map_err(|err| err.to_string());
+    }
+
+    let (Fd(index), rel_path) = preopens
+        .resolve(path.as_bytes())
+        .ok_or("not within a preopened directory")?;
+
+    // `resolve` only strips whole components from a `str`, so the rest is still valid UTF-8
+    let rel_path = str::from_utf8(rel_path).map_err(|err| err.to_string())?;
+    let dir = &dirs[index as usize].0;
+
+    let file = dir
+        .open_at(
+            PathFlags::empty(),
+            rel_path,
+            OpenFlags::empty(),
+            DescriptorFlags::READ,
+        )
+        .map_err(|err| err.to_string())?;
+
+    // The stream must be dropped before the file it reads from
+    let input = file.read_via_stream(0).map_err(|err| err.to_string())?;
+    hash_stream(&input).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod unit_tests;
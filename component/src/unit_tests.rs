@@ -0,0 +1,29 @@
+use super::*;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_updates_and_reset_on_finalize() -> Result<(), String> {
+    let hasher = <Hasher as GuestHasher>::new();
+    hasher.update(b"The quick brown fox ".to_vec());
+    hasher.update(b"jumps over the lazy dog".to_vec());
+
+    let digest = hasher.finalize();
+    let ans = Sha256::digest(b"The quick brown fox jumps over the lazy dog");
+
+    if digest != ans.as_bytes() {
+        return Err(format!(
+            "hasher.finalize() returned {digest:02x?}, expected {ans}"
+        ));
+    }
+
+    let digest = hasher.finalize();
+    let ans = Sha256::digest(b"");
+
+    if digest != ans.as_bytes() {
+        return Err(format!(
+            "a second finalize() returned {digest:02x?}, expected {ans}"
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// SHA256 for browsers and Node, using the reactor module built by `./build.sh reactor`
+//
+//   Browser: const sha256 = await Sha256.load(await (await fetch("sha256_reactor.opt.wasm")).arrayBuffer())
+//   Node:    const sha256 = await Sha256.load(await readFile("sha256_reactor.opt.wasm"))
+//
+//   Sha256.toHex(sha256.digest(new TextEncoder().encode("abc")))
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+interface Exports {
+  memory: WebAssembly.Memory
+  sha256_init(): void
+  sha256_update(ptr: number, len: number): void
+  sha256_final(outPtr: number): void
+  sha256_alloc(len: number): number
+  sha256_free(ptr: number, len: number): void
+}
+
+const DIGEST_LEN = 32
+const CHUNK_SIZE = 64 * 1024
+
+export class Sha256 {
+  private readonly exports: Exports
+
+  // One buffer in linear memory, reused for every piece of input and for the digest
+  private readonly bufPtr: number
+
+  private constructor(exports: Exports) {
+    this.exports = exports
+    this.bufPtr = exports.sha256_alloc(CHUNK_SIZE)
+  }
+
+  // The module imports nothing, so it is instantiated with an empty import object
+  static async load(wasm: BufferSource): Promise<Sha256> {
+    const { instance } = await WebAssembly.instantiate(wasm, {})
+    return new Sha256(instance.exports as unknown as Exports)
+  }
+
+  // Discard any message in progress
+  init(): void {
+    this.exports.sha256_init()
+  }
+
+  // Add data to the message, copying it into linear memory a chunk at a time
+  update(data: Uint8Array): void {
+    for (let start = 0; start < data.length; start += CHUNK_SIZE) {
+      const chunk = data.subarray(start, start + CHUNK_SIZE)
+
+      // Memory can grow between calls, which detaches any earlier view of it
+      new Uint8Array(this.exports.memory.buffer, this.bufPtr, chunk.length).set(chunk)
+      this.exports.sha256_update(this.bufPtr, chunk.length)
+    }
+  }
+
+  // Return the 32 byte digest of the message, then start a new message
+  final(): Uint8Array {
+    this.exports.sha256_final(this.bufPtr)
+    return new Uint8Array(this.exports.memory.buffer, this.bufPtr, DIGEST_LEN).slice()
+  }
+
+  digest(data: Uint8Array): Uint8Array {
+    this.init()
+    this.update(data)
+    return this.final()
+  }
+
+  static toHex(digest: Uint8Array): string {
+    return Array.from(digest, b => b.toString(16).padStart(2, "0")).join("")
+  }
+}
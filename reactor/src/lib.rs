@@ -0,0 +1,81 @@
+use sha256::sha256::Sha256;
+use std::sync::{Mutex, MutexGuard};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Reactor module for JavaScript hosts
+//
+// Built for `wasm32-unknown-unknown`, this module has no `main` and imports nothing, so a browser or Node can
+// instantiate it with an empty import object.  The host copies each piece of the message into a buffer it allocates in
+// linear memory, then passes the buffer to `sha256_update`:
+//
+//   sha256_init()                    Start a new message
+//   sha256_update(ptr, len)          Add `len` bytes at `ptr` to the message
+//   sha256_final(out_ptr)            Write the 32 byte digest to `out_ptr` and start a new message
+//   sha256_alloc(len) -> ptr         Allocate a buffer of `len` bytes
+//   sha256_free(ptr, len)            Release a buffer from `sha256_alloc`
+//
+// The hasher is built on the `phase_1`/`phase_2` compression functions, and there is one hash in progress at a time
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+const DIGEST_LEN: usize = 32;
+
+/// `None` until the first update after `sha256_init` or `sha256_final`
+static HASHER: Mutex<Option<Sha256>> = Mutex::new(None);
+
+fn hasher() -> MutexGuard<'static, Option<Sha256>> {
+    // A panic aborts a WebAssembly module, so the lock can only be poisoned in native tests
+    HASHER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Discard any message in progress
+#[unsafe(no_mangle)]
+pub extern "C" fn sha256_init() {
+    *hasher() = None;
+}
+
+/// Add `len` bytes at `ptr` to the message
+///
+/// # Safety
+/// `ptr` must point to `len` readable bytes, such as a buffer from `sha256_alloc`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sha256_update(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+
+    let data = unsafe { std::slice::from_raw_parts(ptr, len) };
+    hasher().get_or_insert_with(Sha256::new).update(data);
+}
+
+/// Write the digest of the message to `out_ptr`, then start a new message
+///
+/// # Safety
+/// `out_ptr` must point to 32 writable bytes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sha256_final(out_ptr: *mut u8) {
+    let digest = hasher().take().unwrap_or_default().finalize();
+
+    unsafe { out_ptr.copy_from_nonoverlapping(digest.as_bytes().as_ptr(), DIGEST_LEN) };
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Allocate a zeroed buffer of `len` bytes in linear memory
+#[unsafe(no_mangle)]
+pub extern "C" fn sha256_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()).cast()
+}
+
+/// Release a buffer
+///
+/// # Safety
+/// `ptr` and `len` must be a pointer returned by `sha256_alloc` and the length it was given, and the buffer must not
+/// be used again
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sha256_free(ptr: *mut u8, len: usize) {
+    drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) });
+}
+
+#[cfg(test)]
+mod unit_tests;
@@ -0,0 +1,123 @@
+use super::*;
+use sha256::sha256::Digest;
+use std::{fs, path::Path};
+use wasmi::{Engine, Linker, Module, Store, TypedFunc};
+
+static ABC_DIGEST: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+static EMPTY_DIGEST: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Built by `./build.sh reactor`
+static MODULE_PATH: &str = "../target/wasm32-unknown-unknown/release/sha256_reactor.opt.wasm";
+
+fn to_hex(bytes: &[u8; DIGEST_LEN]) -> String {
+    Digest(*bytes).to_string()
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_through_native_exports() -> Result<(), String> {
+    let mut digest = [0u8; DIGEST_LEN];
+    let buf = sha256_alloc(3);
+
+    unsafe {
+        buf.copy_from_nonoverlapping(b"abc".as_ptr(), 3);
+
+        sha256_init();
+        sha256_update(buf, 1);
+        sha256_update(buf.add(1), 2);
+        sha256_final(digest.as_mut_ptr());
+        sha256_free(buf, 3);
+    }
+
+    if to_hex(&digest) != ABC_DIGEST {
+        return Err(format!("sha256(\"abc\") returned {}", to_hex(&digest)));
+    }
+
+    // Finalizing starts a new message
+    unsafe { sha256_final(digest.as_mut_ptr()) };
+
+    if to_hex(&digest) != EMPTY_DIGEST {
+        return Err(format!("sha256(\"\") returned {}", to_hex(&digest)));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Load the WebAssembly module into an embedded runtime and drive its exports as a JavaScript host would
+#[test]
+#[ignore = "needs the WebAssembly module: run ./build.sh reactor first"]
+fn should_hash_through_wasm_exports() -> Result<(), String> {
+    let module_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(MODULE_PATH);
+    let wasm = fs::read(&module_path).map_err(|e| format!("{}: {e}", module_path.display()))?;
+
+    let engine = Engine::default();
+    let module = Module::new(&engine, &wasm).map_err(|e| e.to_string())?;
+    let imports: Vec<String> = module
+        .imports()
+        .map(|import| format!("{}::{}", import.module(), import.name()))
+        .collect();
+
+    if !imports.is_empty() {
+        return Err(format!(
+            "the module should import nothing, but imports {imports:?}"
+        ));
+    }
+
+    let mut store = Store::new(&engine, ());
+    let instance = Linker::<()>::new(&engine)
+        .instantiate(&mut store, &module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| e.to_string())?;
+
+    let memory = instance
+        .get_memory(&store, "memory")
+        .ok_or("the module does not export its memory")?;
+    let init: TypedFunc<(), ()> = instance
+        .get_typed_func(&store, "sha256_init")
+        .map_err(|e| e.to_string())?;
+    let update: TypedFunc<(u32, u32), ()> = instance
+        .get_typed_func(&store, "sha256_update")
+        .map_err(|e| e.to_string())?;
+    let finalize: TypedFunc<u32, ()> = instance
+        .get_typed_func(&store, "sha256_final")
+        .map_err(|e| e.to_string())?;
+    let alloc: TypedFunc<u32, u32> = instance
+        .get_typed_func(&store, "sha256_alloc")
+        .map_err(|e| e.to_string())?;
+    let free: TypedFunc<(u32, u32), ()> = instance
+        .get_typed_func(&store, "sha256_free")
+        .map_err(|e| e.to_string())?;
+
+    // Hash "abc" in two pieces
+    let mut digest = [0u8; DIGEST_LEN];
+    let buf = alloc.call(&mut store, 3).map_err(|e| e.to_string())?;
+    let out = alloc
+        .call(&mut store, DIGEST_LEN as u32)
+        .map_err(|e| e.to_string())?;
+
+    memory
+        .write(&mut store, buf as usize, b"abc")
+        .map_err(|e| e.to_string())?;
+    init.call(&mut store, ()).map_err(|e| e.to_string())?;
+    update
+        .call(&mut store, (buf, 1))
+        .map_err(|e| e.to_string())?;
+    update
+        .call(&mut store, (buf + 1, 2))
+        .map_err(|e| e.to_string())?;
+    finalize.call(&mut store, out).map_err(|e| e.to_string())?;
+    memory
+        .read(&store, out as usize, &mut digest)
+        .map_err(|e| e.to_string())?;
+
+    free.call(&mut store, (buf, 3)).map_err(|e| e.to_string())?;
+    free.call(&mut store, (out, DIGEST_LEN as u32))
+        .map_err(|e| e.to_string())?;
+
+    if to_hex(&digest) != ABC_DIGEST {
+        return Err(format!("sha256(\"abc\") returned {}", to_hex(&digest)));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use crate::{encoding::*, sha256::*, tree_hash};
+
+use std::{
+    fmt,
+    io::{self, Read},
+    mem,
+    path::Path,
+};
+
+const CHUNK_SIZE: usize = 2 * 1024 * 1024; // 2 MiB read buffer
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Amazon S3 and S3 Glacier checksums
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+
+/// The `x-amz-checksum-sha256` value S3 reports for an object uploaded in several parts: the SHA256 of the
+/// concatenated binary part hashes, displayed in base64 followed by `-<number of parts>`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct S3MultipartChecksum {
+    pub digest: Digest,
+    pub parts: usize,
+}
+
+impl fmt::Display for S3MultipartChecksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            base64_encode(self.digest.as_bytes()),
+            self.parts
+        )
+    }
+}
+
+pub fn s3_multipart_checksum(part_hashes: &[Digest]) -> S3MultipartChecksum {
+    let mut hasher = Sha256::new();
+
+    for part_hash in part_hashes {
+        hasher.update(part_hash.as_bytes());
+    }
+
+    S3MultipartChecksum {
+        digest: hasher.finalize(),
+        parts: part_hashes.len(),
+    }
+}
+
+/// The `x-amz-checksum-sha256` value of an object uploaded in a single request
+pub fn s3_checksum(digest: &Digest) -> String {
+    base64_encode(digest.as_bytes())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Read the input in 2 MiB chunks and hash every `part_size` bytes as a separate part.
+/// An empty input is still uploaded as one (empty) part
+pub fn part_hashes(reader: &mut impl Read, part_size: u64) -> io::Result<Vec<Digest>> {
+    if part_size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "part size must be greater than zero",
+        ));
+    }
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut part_hashes = Vec::new();
+    let mut hasher = Sha256::new();
+    let mut part_remaining = part_size;
+
+    loop {
+        let bytes_read = match reader.read(&mut buffer) {
+            Ok(0) => break, // EOF
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        let mut data = &buffer[..bytes_read];
+
+        // A chunk may span one or more part boundaries
+        while !data.is_empty() {
+            let n = part_remaining.min(data.len() as u64) as usize;
+            hasher.update(&data[..n]);
+            data = &data[n..];
+            part_remaining -= n as u64;
+
+            if part_remaining == 0 {
+                part_hashes.push(mem::take(&mut hasher).finalize());
+                part_remaining = part_size;
+            }
+        }
+    }
+
+    if part_remaining < part_size || part_hashes.is_empty() {
+        part_hashes.push(hasher.finalize());
+    }
+
+    Ok(part_hashes)
+}
+
+/// The S3 multipart checksum of a local file uploaded in parts of `part_size` bytes
+pub fn s3_multipart_file(path: &Path, part_size: u64) -> io::Result<S3MultipartChecksum> {
+    let mut file = std::fs::File::open(path)?;
+    Ok(s3_multipart_checksum(&part_hashes(&mut file, part_size)?))
+}
+
+/// The `x-amz-sha256-tree-hash` value of a local file (hex encoded when displayed)
+pub fn glacier_tree_hash_file(path: &Path, threads: usize) -> io::Result<Digest> {
+    tree_hash::hash_file(path, tree_hash::TreeHash::Glacier, threads)
+}
+
+#[cfg(test)]
+mod unit_tests;
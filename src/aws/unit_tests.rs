@@ -0,0 +1,53 @@
+use super::*;
+
+fn test_data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_calculate_s3_multipart_checksum() -> Result<(), String> {
+    let data = test_data(10000);
+    let ans = "kpC06Wa+R26oYCjoY+j31aEB72X4P67AxAt74hw9Ua4=-3";
+
+    let part_hashes = part_hashes(&mut data.as_slice(), 4096).map_err(|e| e.to_string())?;
+    let checksum = s3_multipart_checksum(&part_hashes).to_string();
+
+    if checksum != ans {
+        return Err(format!(
+            "S3 multipart checksum was {checksum}, expected {ans}"
+        ));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_split_parts_across_read_chunks() -> Result<(), String> {
+    // Parts that are not aligned with the 2 MiB read buffer, including an exact multiple of the part size
+    let part_size = 3 * 1024 * 1024 + 7;
+    let data = test_data(3 * part_size);
+    let ans: Vec<Digest> = data.chunks(part_size).map(Sha256::digest).collect();
+
+    let hashes = part_hashes(&mut data.as_slice(), part_size as u64).map_err(|e| e.to_string())?;
+
+    if hashes != ans {
+        return Err(format!("Part hashes were {hashes:?}, expected {ans:?}"));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_calculate_single_part_checksum() -> Result<(), String> {
+    let ans = "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=";
+    let checksum = s3_checksum(&Sha256::digest(b"abc"));
+
+    if checksum != ans {
+        return Err(format!("S3 checksum was {checksum}, expected {ans}"));
+    }
+
+    Ok(())
+}
@@ -1,123 +1,887 @@
-use sha256::sha256::*;
-
+#[cfg(unix)]
+use sha256::mmap::*;
+use sha256::{aws::*, encoding::*, git, manifest, report::*, sha256::*, sha256d::*, tree_hash::*};
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
 use std::{
     env,
+    ffi::{OsStr, OsString},
     fs::File,
-    io::{self, BufReader, Read},
-    process,
+    io::{self, IsTerminal, Read, Write},
+    path::Path,
+    process, thread,
+    time::{Duration, Instant},
 };
 
 const MAX_SIZE: u64 = 4 * 1024 * 1024 * 1024; // 4 GiB file size limit
 const CHUNK_SIZE: usize = 2 * 1024 * 1024; // 2 MiB chunk size
-const MSG_BLKS_PER_CHUNK: usize = CHUNK_SIZE >> 6;
+const MMAP_THRESHOLD: u64 = 64 * 1024 * 1024; // Files of 64 MiB or more are memory mapped
+#[cfg(unix)]
+const MMAP_WINDOW: usize = 64 * 1024 * 1024; // Check for truncation after every 64 MiB mapped window
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100); // Redraw the progress bar at most 10 times a second
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Sha256,
+    Double,
+    GitBlob,
+    TreeHash(TreeHash),
+    S3Multipart(u64),
+}
 
-static HEX_CHARS: [char; 16] = [
-    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f',
-];
+struct Options {
+    mode: Mode,
+    force_mmap: bool,
+    encoding: Encoding,
+    format: Format,
+    zero: bool,
+    progress: bool,
+    stats: bool,
+}
 
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
 fn main() -> io::Result<()> {
-    let mut msg_digest = Box::new([0u32; 64]);
+    let args: Vec<OsString> = env::args_os().collect();
+    let bin_name = args[0].to_string_lossy();
+    let mut opts = Options {
+        mode: Mode::Sha256,
+        force_mmap: false,
+        encoding: Encoding::Hex,
+        format: Format::Text,
+        zero: false,
+        progress: false,
+        stats: false,
+    };
+    let mut check = false;
+    let mut expect = None;
+    let mut sources: Vec<Source> = Vec::new();
+
+    let mut arg_iter = args[1..].iter();
+    while let Some(arg) = arg_iter.next() {
+        let Some(opt) = arg.to_str() else {
+            sources.push(Source::File(arg));
+            continue;
+        };
+
+        match opt {
+            "--mmap" => opts.force_mmap = true,
+            "-z" | "--zero" => opts.zero = true,
+            "-c" | "--check" => check = true,
+            // A progress bar would only garble a log file or pipe
+            "--progress" => opts.progress = io::stderr().is_terminal(),
+            "--stats" => opts.stats = true,
+            "--expect" => match arg_iter.next().map(|s| parse_expected(s, &bin_name)) {
+                Some(expected) => expect = Some(expected),
+                None => usage(&bin_name),
+            },
+            _ if opt.starts_with("--expect=") => {
+                expect = Some(parse_expected(OsStr::new(&opt[9..]), &bin_name))
+            }
+            "--string" | "--hex-input" | "--base64-input" => match arg_iter.next() {
+                Some(value) => sources.push(data_source(opt, value, &bin_name)),
+                None => usage(&bin_name),
+            },
+            _ if matches!(
+                opt.split_once('='),
+                Some(("--string" | "--hex-input" | "--base64-input", _))
+            ) =>
+            {
+                let (name, value) = opt.split_once('=').unwrap_or_default();
+                sources.push(data_source(name, OsStr::new(value), &bin_name));
+            }
+            "--double" => set_mode(&mut opts, Mode::Double, &bin_name),
+            "--git-blob" => set_mode(&mut opts, Mode::GitBlob, &bin_name),
+            "--tree-hash" => set_mode(&mut opts, Mode::TreeHash(TreeHash::V1), &bin_name),
+            _ if opt.starts_with("--tree-hash=") => match TreeHash::from_name(&opt[12..]) {
+                Some(th) => set_mode(&mut opts, Mode::TreeHash(th), &bin_name),
+                None => usage(&bin_name),
+            },
+            "--s3-multipart" => match arg_iter.next().and_then(|s| parse_size(s.to_str()?)) {
+                Some(part_size) => set_mode(&mut opts, Mode::S3Multipart(part_size), &bin_name),
+                None => usage(&bin_name),
+            },
+            "--encoding" => match arg_iter
+                .next()
+                .and_then(|s| Encoding::from_name(s.to_str()?))
+            {
+                Some(enc) => opts.encoding = enc,
+                None => usage(&bin_name),
+            },
+            _ if opt.starts_with("--encoding=") => match Encoding::from_name(&opt[11..]) {
+                Some(enc) => opts.encoding = enc,
+                None => usage(&bin_name),
+            },
+            "--format" => match arg_iter.next().and_then(|s| Format::from_name(s.to_str()?)) {
+                Some(fmt) => opts.format = fmt,
+                None => usage(&bin_name),
+            },
+            _ if opt.starts_with("--format=") => match Format::from_name(&opt[9..]) {
+                Some(fmt) => opts.format = fmt,
+                None => usage(&bin_name),
+            },
+            _ if opt.starts_with("--") => usage(&bin_name),
+            _ => sources.push(Source::File(arg)),
+        }
+    }
+
+    // Zero-terminated and check modes only apply to text manifests, which cannot hold raw digests.
+    // Manifests can only be read from files
+    let text_only = opts.zero || check;
+
+    if sources.is_empty()
+        || (text_only && opts.format != Format::Text)
+        || (check && opts.encoding == Encoding::Raw)
+        || (check
+            && sources
+                .iter()
+                .any(|source| matches!(source, Source::Data { .. })))
+        || (expect.is_some()
+            && (sources.len() > 1 || check || opts.zero || opts.format != Format::Text))
+        || (expect.is_some() && matches!(opts.mode, Mode::S3Multipart(_)))
+    {
+        usage(&bin_name);
+    }
+
+    let run_start = Instant::now();
+    let mut stats = Stats::default();
 
-    // The first 32 bits of the fractional part of the square roots of the first 8 primes 2..19
-    let mut hash_vals: [u32; 8] = [
-        0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB,
-        0x5BE0CD19,
-    ];
+    if check || expect.is_some() {
+        let all_ok = match expect {
+            Some((encoding, expected)) => expect_digest(
+                &sources[0],
+                expected,
+                encoding,
+                &opts,
+                &bin_name,
+                &mut stats,
+            )?,
+            None => {
+                let manifests: Vec<&OsStr> = sources
+                    .iter()
+                    .filter_map(|source| match source {
+                        Source::File(path) => Some(*path),
+                        Source::Data { .. } => None,
+                    })
+                    .collect();
+
+                check_manifests(&manifests, &opts, &bin_name, &mut stats)?
+            }
+        };
+
+        if opts.stats {
+            write_stats(&bin_name, &stats, run_start);
+        }
+
+        if !all_ok {
+            process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    let mut stdout = io::stdout().lock();
+    let mut out: Vec<u8> = Vec::new();
+    let mut failed = false;
+
+    opts.format.write_header(&mut out);
+
+    for (index, source) in sources.iter().enumerate() {
+        let start = Instant::now();
+        let result = digest_source(source, &opts);
+        let elapsed_us = start.elapsed().as_micros() as u64;
+
+        let error_msg;
+        let mut rec = Record {
+            path: source.name(),
+            size: None,
+            algorithm: algorithm_name(opts.mode),
+            digest: None,
+            elapsed_us,
+            error: None,
+        };
+
+        if let Ok((_, size)) = &result {
+            stats.add(*size);
+        }
 
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: {} <filename>", args[0]);
+        match &result {
+            // Raw digests are written on their own so they can be piped straight into other tools
+            Ok((digest, _)) if opts.format == Format::Text && opts.encoding == Encoding::Raw => {
+                out.extend_from_slice(digest);
+            }
+            Ok((digest, _)) if opts.format == Format::Text => {
+                manifest::write_entry(&mut out, digest, rec.path, opts.zero);
+            }
+            Ok((digest, size)) => {
+                rec.digest = Some(digest);
+                rec.size = Some(*size);
+                opts.format.write_record(&mut out, index, &rec);
+            }
+            Err(e) => {
+                failed = true;
+                error_msg = e.to_string();
+                rec.error = Some(error_msg.as_bytes());
+
+                if opts.format == Format::Text {
+                    eprintln!("{bin_name}: {}: {e}", display_name(rec.path));
+                }
+
+                opts.format.write_record(&mut out, index, &rec);
+            }
+        }
+
+        stdout.write_all(&out)?;
+        out.clear();
+    }
+
+    opts.format.write_footer(&mut out, sources.len());
+    stdout.write_all(&out)?;
+    stdout.flush()?;
+
+    if opts.stats {
+        write_stats(&bin_name, &stats, run_start);
+    }
+
+    if failed {
         process::exit(1);
     }
-    let filename = &args[1];
 
-    // Check file size first
-    let metadata = std::fs::metadata(filename)?;
-    let file_size = metadata.len();
-    if file_size >= MAX_SIZE {
-        eprintln!(
-            "Error: file '{}' is too large ({} bytes, max allowed is < 4 GiB)",
-            filename, file_size
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+fn usage(bin_name: &str) -> ! {
+    eprintln!(
+        "Usage: {bin_name} [--mmap] [--double | --tree-hash[=v1|glacier] | --s3-multipart PART_SIZE | --git-blob]\n       \
+         {:width$}[--encoding ENCODING] [--format text|json|jsonl|csv] [-z] [--progress] [--stats] <input>...\n       \
+         {bin_name} --check [-z] [--encoding ENCODING] [--progress] [--stats] [<mode option>] <manifest>...\n       \
+         {bin_name} --expect DIGEST [--progress] [--stats] [<mode option>] <input>\n\n\
+         Each input is a filename, - for stdin, or data given as --string TEXT, --hex-input HEX or --base64-input BASE64",
+        "",
+        width = bin_name.len() + 1
+    );
+    process::exit(1);
+}
+
+/// Only one of the options that select an alternative to plain SHA256 may be given
+fn set_mode(opts: &mut Options, mode: Mode, bin_name: &str) {
+    if opts.mode != Mode::Sha256 {
+        usage(bin_name);
+    }
+
+    opts.mode = mode;
+}
+
+fn algorithm_name(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Sha256 => "sha256",
+        Mode::Double => "sha256d",
+        Mode::GitBlob => "git-blob-sha256",
+        Mode::TreeHash(TreeHash::V1) => "sha256-tree-v1",
+        Mode::TreeHash(TreeHash::Glacier) => "sha256-tree-glacier",
+        Mode::S3Multipart(_) => "s3-multipart-sha256",
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Verify every entry in the given manifests, printing `<path>: OK` or `<path>: FAILED` for each one.
+/// A manifest named `-` is read from stdin.  Returns false if any file failed to verify
+fn check_manifests(
+    manifests: &[&OsStr],
+    opts: &Options,
+    bin_name: &str,
+    stats: &mut Stats,
+) -> io::Result<bool> {
+    let mut stdout = io::stdout().lock();
+    let mut all_ok = true;
+
+    for &manifest_name in manifests {
+        let mut contents = Vec::new();
+        let read = if manifest_name == "-" {
+            io::stdin().lock().read_to_end(&mut contents)
+        } else {
+            File::open(manifest_name).and_then(|mut f| f.read_to_end(&mut contents))
+        };
+
+        if let Err(e) = read {
+            eprintln!(
+                "{bin_name}: {}: {e}",
+                display_name(manifest_name.as_encoded_bytes())
+            );
+            all_ok = false;
+            continue;
+        }
+
+        let (mut checked, mut malformed, mut unreadable, mut mismatched) = (0, 0, 0, 0);
+
+        for line in contents.split(|&b| b == manifest::line_terminator(opts.zero)) {
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some(entry) = manifest::parse_line(line, opts.zero) else {
+                malformed += 1;
+                continue;
+            };
+            let Some(path) = path_from_bytes(&entry.path) else {
+                malformed += 1;
+                continue;
+            };
+
+            checked += 1;
+            let name = display_name(&entry.path);
+
+            let result = digest_source(&Source::File(path.as_os_str()), opts);
+
+            if let Ok((_, size)) = result {
+                stats.add(size);
+            }
+
+            let status = match result {
+                Ok((digest, _)) if digest_matches(&digest, entry.digest, opts.encoding) => "OK",
+                Ok(_) => {
+                    mismatched += 1;
+                    "FAILED"
+                }
+                Err(e) => {
+                    eprintln!("{bin_name}: {name}: {e}");
+                    unreadable += 1;
+                    "FAILED open or read"
+                }
+            };
+
+            writeln!(stdout, "{name}: {status}")?;
+        }
+
+        stdout.flush()?;
+
+        if checked == 0 {
+            eprintln!(
+                "{bin_name}: {}: no properly formatted checksum lines found",
+                display_name(manifest_name.as_encoded_bytes())
+            );
+            all_ok = false;
+            continue;
+        }
+
+        warn(
+            bin_name,
+            malformed,
+            "line is improperly formatted",
+            "lines are improperly formatted",
         );
-        process::exit(1);
+        warn(
+            bin_name,
+            unreadable,
+            "listed file could not be read",
+            "listed files could not be read",
+        );
+        warn(
+            bin_name,
+            mismatched,
+            "computed checksum did NOT match",
+            "computed checksums did NOT match",
+        );
+        all_ok &= unreadable == 0 && mismatched == 0;
+    }
+
+    Ok(all_ok)
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// The digest given to `--expect`, in any text encoding, and the encoding it was written in
+fn parse_expected(text: &OsStr, bin_name: &str) -> (Encoding, Digest) {
+    match Encoding::detect(text.as_encoded_bytes().trim_ascii()) {
+        Some(expected) => expected,
+        None => {
+            eprintln!(
+                "{bin_name}: {}: not a SHA256 digest in any supported encoding",
+                display_name(text.as_encoded_bytes())
+            );
+            process::exit(1);
+        }
+    }
+}
+
+/// Hash one input and compare it with the `--expect` digest, printing `<name>: OK` or `<name>: FAILED`.
+/// After a mismatch, both digests are shown on stderr in the encoding the expected one was given in.
+/// Returns false if the digests differ or the file could not be read
+fn expect_digest(
+    source: &Source,
+    expected: Digest,
+    encoding: Encoding,
+    opts: &Options,
+    bin_name: &str,
+    stats: &mut Stats,
+) -> io::Result<bool> {
+    let name = display_name(source.name());
+
+    let actual = match hash_source(source, opts) {
+        Ok((digest, size)) => {
+            stats.add(size);
+            *digest.digest()
+        }
+        Err(e) => {
+            eprintln!("{bin_name}: {name}: {e}");
+            println!("{name}: FAILED open or read");
+            return Ok(false);
+        }
+    };
+
+    if actual.ct_eq(&expected) {
+        println!("{name}: OK");
+        return Ok(true);
+    }
+
+    println!("{name}: FAILED");
+    eprint!(
+        "{bin_name}: {name}: computed checksum did NOT match\n{}",
+        mismatch(&encoding.encode(&expected), &encoding.encode(&actual))
+    );
+
+    Ok(false)
+}
+
+/// The expected and actual digests on consecutive lines, followed by a line marking each character that differs
+fn mismatch(expected: &[u8], actual: &[u8]) -> String {
+    let markers: String = expected
+        .iter()
+        .zip(actual)
+        .map(|(e, a)| if e == a { ' ' } else { '^' })
+        .collect();
+
+    format!(
+        "  expected: {}\n  actual:   {}\n            {}\n",
+        String::from_utf8_lossy(expected),
+        String::from_utf8_lossy(actual),
+        markers.trim_end()
+    )
+}
+
+/// Summarise the run on stderr, so that stdout remains a manifest
+fn write_stats(bin_name: &str, stats: &Stats, run_start: Instant) {
+    let mut out = format!("{bin_name}: ").into_bytes();
+    stats.write(&mut out, run_start.elapsed().as_micros() as u64);
+    let _ = io::stderr().write_all(&out);
+}
+
+fn warn(bin_name: &str, count: usize, singular: &str, plural: &str) {
+    match count {
+        0 => {}
+        1 => eprintln!("{bin_name}: WARNING: 1 {singular}"),
+        n => eprintln!("{bin_name}: WARNING: {n} {plural}"),
+    }
+}
+
+/// Hex digests may be written in either case
+fn digest_matches(actual: &[u8], expected: &[u8], encoding: Encoding) -> bool {
+    match encoding {
+        Encoding::Hex | Encoding::UpperHex => actual.eq_ignore_ascii_case(expected),
+        _ => actual == expected,
+    }
+}
+
+/// A path for a message on a single line, escaped as in a manifest
+fn display_name(path: &[u8]) -> String {
+    let mut name = Vec::new();
+    manifest::write_name(&mut name, path);
+    String::from_utf8_lossy(&name).into_owned()
+}
+
+/// Paths are arbitrary bytes on Unix, but must be valid UTF-8 elsewhere to be read from a manifest
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> Option<&Path> {
+    Some(Path::new(OsStr::from_bytes(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> Option<&Path> {
+    str::from_utf8(bytes).ok().map(Path::new)
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Something to hash: a file, or data given on the command line
+enum Source<'a> {
+    File(&'a OsStr),
+    /// Named after the option that gave it, e.g. `--hex-input=616263`, so that it cannot be mistaken for a file
+    Data {
+        name: Vec<u8>,
+        data: Vec<u8>,
+    },
+}
+
+impl Source<'_> {
+    fn name(&self) -> &[u8] {
+        match self {
+            Source::File(path) => path.as_encoded_bytes(),
+            Source::Data { name, .. } => name,
+        }
+    }
+}
+
+/// The data given by `--string`, `--hex-input` or `--base64-input`.
+/// Whitespace is ignored in hex and base64, so a hex dump can be pasted in as it is
+fn data_source(opt: &str, value: &OsStr, bin_name: &str) -> Source<'static> {
+    let text = value.as_encoded_bytes();
+    let packed: Vec<u8> = text
+        .iter()
+        .copied()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+
+    let (data, kind) = match opt {
+        "--hex-input" => (hex_decode(&packed), "hex"),
+        "--base64-input" => (base64_decode(&packed), "base64"),
+        _ => (Some(text.to_vec()), "text"),
+    };
+
+    let mut name = format!("{opt}=").into_bytes();
+    name.extend_from_slice(text);
+
+    match data {
+        Some(data) => Source::Data { name, data },
+        None => {
+            eprintln!("{bin_name}: {}: not valid {kind}", display_name(&name));
+            process::exit(1);
+        }
     }
+}
+
+/// Hash one input, returning the encoded digest and its size
+fn digest_source(source: &Source, opts: &Options) -> io::Result<(Vec<u8>, u64)> {
+    let (digest, size) = hash_source(source, opts)?;
+
+    let encoded = match digest {
+        // S3 multipart checksums are always displayed in base64 with a part count suffix
+        FileDigest::S3Multipart(checksum) => checksum.to_string().into_bytes(),
+
+        // Raw bytes cannot be embedded in structured output
+        FileDigest::Digest(digest)
+            if opts.encoding == Encoding::Raw && opts.format != Format::Text =>
+        {
+            Encoding::Hex.encode(&digest)
+        }
+        FileDigest::Digest(digest) => opts.encoding.encode(&digest),
+    };
+
+    Ok((encoded, size))
+}
+
+/// What hashing an input produces.  An S3 multipart checksum also records the number of parts
+enum FileDigest {
+    Digest(Digest),
+    S3Multipart(S3MultipartChecksum),
+}
+
+impl FileDigest {
+    fn digest(&self) -> &Digest {
+        match self {
+            FileDigest::Digest(digest) => digest,
+            FileDigest::S3Multipart(checksum) => &checksum.digest,
+        }
+    }
+}
+
+/// Hash one input, returning the digest as it is displayed and its size
+fn hash_source(source: &Source, opts: &Options) -> io::Result<(FileDigest, u64)> {
+    let (digest, size) = match source {
+        Source::File(path) => hash_path(Path::new(path), opts)?,
+        Source::Data { data, .. } => (hash_data(data, opts.mode)?, data.len() as u64),
+    };
+
+    // Bitcoin-style SHA256(SHA256(x)), displayed with its bytes reversed
+    let digest = match digest {
+        FileDigest::Digest(digest) if opts.mode == Mode::Double => {
+            FileDigest::Digest(rehash(&digest).reversed())
+        }
+        digest => digest,
+    };
+
+    Ok((digest, size))
+}
+
+/// Hash data given on the command line with the same functions that hash the contents of a file
+fn hash_data(mut data: &[u8], mode: Mode) -> io::Result<FileDigest> {
+    let digest = match mode {
+        Mode::S3Multipart(part_size) => {
+            let checksum = s3_multipart_checksum(&part_hashes(&mut data, part_size)?);
+            return Ok(FileDigest::S3Multipart(checksum));
+        }
+        Mode::GitBlob => git::blob_id(data),
+        Mode::TreeHash(tree_hash) => tree_hash.digest(data),
+        Mode::Sha256 | Mode::Double => hash_buffered(data)?,
+    };
+
+    Ok(FileDigest::Digest(digest))
+}
+
+/// Hash one file, or stdin for `-`, returning its digest and the number of bytes hashed
+fn hash_path(path: &Path, opts: &Options) -> io::Result<(FileDigest, u64)> {
+    if path.as_os_str() == "-" {
+        return hash_stream(io::stdin().lock(), opts);
+    }
+
+    let metadata = std::fs::metadata(path)?;
+
+    // The size of a pipe or special file is given as 0, and is not known until it has been read
+    if !metadata.is_file() {
+        return hash_stream(File::open(path)?, opts);
+    }
+
+    let file_size = metadata.len();
+    let mut progress = Progress::new(opts.progress, file_size);
+
+    let digest = match opts.mode {
+        Mode::S3Multipart(part_size) => {
+            let mut reader = ProgressReader::new(File::open(path)?, &mut progress);
+            let checksum = s3_multipart_checksum(&part_hashes(&mut reader, part_size)?);
+            FileDigest::S3Multipart(checksum)
+        }
+
+        // Git blob object IDs hash the "blob <size>\0" header followed by the file content
+        Mode::GitBlob => {
+            let mut reader = ProgressReader::new(File::open(path)?, &mut progress);
+            FileDigest::Digest(git::blob_id_from_reader(&mut reader, file_size)?)
+        }
+
+        // Tree hashes are calculated in parallel and are not subject to the file size limit.
+        // The chunks are read on other threads, so the progress bar only moves once the whole file is done
+        Mode::TreeHash(tree_hash) => {
+            let threads = thread::available_parallelism().map_or(1, |n| n.get());
+            let digest = hash_file(path, tree_hash, threads)?;
+            progress.advance(file_size);
+            FileDigest::Digest(digest)
+        }
+
+        Mode::Sha256 | Mode::Double => {
+            if file_size >= MAX_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("file is too large ({file_size} bytes, max allowed is < 4 GiB)"),
+                ));
+            }
+
+            let file = File::open(path)?;
+
+            // Empty files cannot be mapped
+            let use_mmap =
+                cfg!(unix) && file_size > 0 && (opts.force_mmap || file_size >= MMAP_THRESHOLD);
+
+            if use_mmap {
+                FileDigest::Digest(hash_mapped(&file, file_size, &mut progress)?)
+            } else {
+                FileDigest::Digest(hash_buffered(ProgressReader::new(file, &mut progress))?)
+            }
+        }
+    };
+
+    Ok((digest, progress.done))
+}
+
+/// Hash a pipe or special file, whose size is only known once it has been read to the end.
+/// Returns the digest and the number of bytes hashed
+fn hash_stream(reader: impl Read, opts: &Options) -> io::Result<(FileDigest, u64)> {
+    let mut progress = Progress::new(opts.progress, 0);
+    let mut reader = ProgressReader::new(reader, &mut progress);
+
+    let digest = match opts.mode {
+        Mode::S3Multipart(part_size) => {
+            let checksum = s3_multipart_checksum(&part_hashes(&mut reader, part_size)?);
+            FileDigest::S3Multipart(checksum)
+        }
 
-    let file_size_bits = (file_size << 3).to_be_bytes();
+        // The blob header holds the size of the content, so the whole of the content must be read before hashing
+        Mode::GitBlob => {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            FileDigest::Digest(git::blob_id(&data))
+        }
+
+        // There is no size to divide into chunks up front, so the chunks are read and hashed in turn
+        Mode::TreeHash(tree_hash) => FileDigest::Digest(hash_reader(&mut reader, tree_hash)?),
+
+        Mode::Sha256 | Mode::Double => FileDigest::Digest(hash_buffered(&mut reader)?),
+    };
+
+    Ok((digest, progress.done))
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// A non-zero size in bytes, optionally followed by a binary multiplier suffix: `K`, `M` or `G`
+fn parse_size(size: &str) -> Option<u64> {
+    let (digits, multiplier) = match size.as_bytes().last()? {
+        b'K' | b'k' => (&size[..size.len() - 1], 1 << 10),
+        b'M' | b'm' => (&size[..size.len() - 1], 1 << 20),
+        b'G' | b'g' => (&size[..size.len() - 1], 1 << 30),
+        _ => (size, 1),
+    };
 
-    // Prepare empty msg block for edge case where file size is an exact integer-multiple of the buffer size
-    let mut empty_msg_blk = [0u8; 64];
-    empty_msg_blk[0] = 0x80;
-    empty_msg_blk[56..].copy_from_slice(&file_size_bits);
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .filter(|&n| n > 0)
+}
 
-    let file = File::open(filename)?;
-    let mut reader = BufReader::new(file);
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Read through a 2 MiB buffer until EOF.
+/// A read may return fewer bytes than asked for at any point (pipes, network filesystems), so only a zero-length read
+/// ends the input and the streaming hasher takes care of the padding
+fn hash_buffered(mut reader: impl Read) -> io::Result<Digest> {
+    let mut hasher = Sha256::new();
 
     // Allocate buffer directly on the heap
     let mut buffer: Box<[u8]> = vec![0u8; CHUNK_SIZE].into_boxed_slice();
-    let mut bytes_remaining = file_size;
 
     loop {
-        let mut extra_blk = false;
-        let bytes_read = reader.read(&mut buffer[..])?;
-        bytes_remaining = bytes_remaining.saturating_sub(bytes_read as u64);
+        let bytes_read = match reader.read(&mut buffer) {
+            Ok(0) => break, // EOF
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(hasher.finalize())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Map the file into memory and pass each 64-byte message block directly to the compression functions
+#[cfg(unix)]
+fn hash_mapped(file: &File, file_size: u64, progress: &mut Progress) -> io::Result<Digest> {
+    let mut msg_schedule = [0u32; 64];
+    let mut hash_vals = H0;
+
+    let map_len = usize::try_from(file_size).map_err(io::Error::other)?;
+    let map = Mmap::map(file, map_len)?;
+    let data = map.as_slice();
+    let tail_idx = map_len & !63;
+
+    // Should another process truncate the file, touching a page beyond the new EOF will raise SIGBUS
+    exit_on_sigbus();
+
+    for window in data[..tail_idx].chunks(MMAP_WINDOW) {
+        check_not_truncated(file, file_size)?;
 
-        if bytes_read == 0 {
-            break; // EOF
+        for msg_blk in window.chunks_exact(64) {
+            phase_1(msg_blk, &mut msg_schedule);
+            phase_2(&msg_schedule, &mut hash_vals);
         }
 
-        // Assume the chunk is full
-        let mut msg_blk_count = MSG_BLKS_PER_CHUNK;
+        progress.advance(window.len() as u64);
+    }
 
-        if bytes_read == CHUNK_SIZE {
-            if bytes_remaining == 0 {
-                // Edge case: file size is exact an integer-multiple of the chunk size
-                extra_blk = true;
-            }
-        } else {
-            // Final partial chunk - which will always contain at least one empty byte for the EOD marker
-            let eod = bytes_read;
-            buffer[eod] = 0x80;
-            let used = eod + 1;
-
-            // Avoid buffer overflow
-            if CHUNK_SIZE - used < 8 {
-                extra_blk = true;
-                empty_msg_blk[0] = 0x00; // EOD marker already exists at the end of the previous block
-                buffer[used..].fill(0);
-            } else {
-                let rem = used % 64;
-                let pad_zeros = if rem <= 56 { 56 - rem } else { 56 + 64 - rem };
-                let end = used + pad_zeros + 8; // total bytes to process from this chunk
+    check_not_truncated(file, file_size)?;
+    phase_final(
+        &data[tail_idx..],
+        file_size,
+        &mut msg_schedule,
+        &mut hash_vals,
+    );
+    progress.advance((map_len - tail_idx) as u64);
 
-                buffer[used..used + pad_zeros].fill(0);
-                buffer[end - 8..end].copy_from_slice(&file_size_bits);
+    Ok(Digest::from_hash_vals(&hash_vals))
+}
 
-                msg_blk_count = end / 64;
-            }
+#[cfg(not(unix))]
+fn hash_mapped(file: &File, _file_size: u64, progress: &mut Progress) -> io::Result<Digest> {
+    hash_buffered(ProgressReader::new(file.try_clone()?, progress))
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[cfg(unix)]
+fn check_not_truncated(file: &File, file_size: u64) -> io::Result<()> {
+    if file.metadata()?.len() < file_size {
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "file truncated while being hashed",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// A progress bar on stderr for the file being hashed, redrawn at most every `PROGRESS_INTERVAL` and erased when dropped.
+/// A disabled bar draws nothing, but still counts the bytes hashed
+struct Progress {
+    enabled: bool,
+    total: u64,
+    done: u64,
+    start: Instant,
+    last_draw: Option<Instant>,
+    line: Vec<u8>,
+}
+
+impl Progress {
+    /// `total` is 0 if the size is not known in advance
+    fn new(enabled: bool, total: u64) -> Progress {
+        Progress {
+            enabled,
+            total,
+            done: 0,
+            start: Instant::now(),
+            last_draw: None,
+            line: Vec::new(),
         }
+    }
+
+    fn advance(&mut self, bytes: u64) {
+        self.done += bytes;
 
-        // Process available message blocks
-        for blk in 0..msg_blk_count {
-            let blk_idx = blk << 6;
-            phase_1(&buffer[blk_idx..blk_idx + 64], &mut msg_digest);
-            phase_2(&msg_digest, &mut hash_vals);
+        if !self.enabled {
+            return;
         }
 
-        // Potential extra message block for edge case
-        if extra_blk {
-            phase_1(&empty_msg_blk, &mut msg_digest);
-            phase_2(&msg_digest, &mut hash_vals);
+        let now = Instant::now();
+
+        // Small files are done before the first redraw is due, so they never draw a bar at all
+        if now.duration_since(self.last_draw.unwrap_or(self.start)) < PROGRESS_INTERVAL {
+            return;
         }
+
+        self.last_draw = Some(now);
+        self.line.clear();
+        write_progress(
+            &mut self.line,
+            self.done,
+            self.total,
+            now.duration_since(self.start).as_micros() as u64,
+        );
+        let _ = io::stderr().write_all(&self.line);
     }
+}
 
-    let mut hex_str = String::with_capacity(64);
-    for &h in &hash_vals {
-        for i in (0..8).rev() {
-            hex_str.push(HEX_CHARS[((h >> (i * 4)) & 0xF) as usize]);
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.last_draw.is_some() {
+            let _ = io::stderr().write_all(CLEAR_LINE);
         }
     }
-    println!("{hex_str}  {filename}");
+}
 
-    Ok(())
+/// Reports every read to a progress bar
+struct ProgressReader<'a, R> {
+    reader: R,
+    progress: &'a mut Progress,
 }
+
+impl<'a, R: Read> ProgressReader<'a, R> {
+    fn new(reader: R, progress: &'a mut Progress) -> ProgressReader<'a, R> {
+        ProgressReader { reader, progress }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes_read = self.reader.read(buf)?;
+        self.progress.advance(bytes_read as u64);
+        Ok(bytes_read)
+    }
+}
+
+#[cfg(test)]
+#[path = "std/unit_tests.rs"]
+mod unit_tests;
@@ -0,0 +1,247 @@
+use super::*;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Hands out its data a pseudo-random number of bytes at a time, as a pipe or network filesystem might, and is
+/// occasionally interrupted
+struct RandomReader<'a> {
+    data: &'a [u8],
+    state: u64,
+}
+
+impl RandomReader<'_> {
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
+
+impl Read for RandomReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let r = self.next();
+
+        if r.is_multiple_of(16) {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+
+        let n = (1 + (r % 100_000) as usize)
+            .min(buf.len())
+            .min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+/// Default options, other than the mode
+fn options(mode: Mode) -> Options {
+    Options {
+        mode,
+        force_mmap: false,
+        encoding: Encoding::Hex,
+        format: Format::Text,
+        zero: false,
+        progress: false,
+        stats: false,
+    }
+}
+
+/// Call `f` with a path to the read end of a pipe, while another thread writes `data` into the pipe
+#[cfg(unix)]
+fn with_pipe<T>(data: &[u8], f: impl FnOnce(&Path) -> T) -> io::Result<T> {
+    use std::os::fd::AsRawFd;
+
+    let (reader, mut writer) = io::pipe()?;
+    let path = std::path::PathBuf::from(format!("/dev/fd/{}", reader.as_raw_fd()));
+
+    Ok(thread::scope(|s| {
+        s.spawn(move || writer.write_all(data));
+        let result = f(&path);
+
+        // Should `f` stop reading early, closing the last read end makes the writer fail rather than block
+        drop(reader);
+        result
+    }))
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_despite_random_read_lengths() -> Result<(), String> {
+    // Spans several chunks, and sizes either side of a whole number of chunks and message blocks
+    let data: Vec<u8> = (0..2 * CHUNK_SIZE as u32 + 100)
+        .map(|i| (i * 31 + i / 251) as u8)
+        .collect();
+
+    for len in [
+        0,
+        1,
+        55,
+        56,
+        64,
+        CHUNK_SIZE - 1,
+        CHUNK_SIZE,
+        CHUNK_SIZE + 1,
+        data.len(),
+    ] {
+        for seed in [1, 0x9e37_79b9_7f4a_7c15, 0xdead_beef] {
+            let reader = RandomReader {
+                data: &data[..len],
+                state: seed,
+            };
+            let digest = hash_buffered(reader).map_err(|e| e.to_string())?;
+
+            if digest != Sha256::digest(&data[..len]) {
+                return Err(format!(
+                    "{len} bytes with seed {seed:#x}: wrong digest {digest}"
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_mark_mismatched_characters() -> Result<(), String> {
+    let diff = mismatch(b"sha256-abcd=", b"sha256-abXd=");
+    let ans = "  expected: sha256-abcd=\n  actual:   sha256-abXd=\n                     ^\n";
+
+    if diff != ans {
+        return Err(format!("got {diff:?}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_hash_data_like_a_file() -> Result<(), String> {
+    let mut opts = options(Mode::Sha256);
+
+    // NIST FIPS 180-2 "abc" given as text, hex dump and base64
+    let sources = [
+        data_source("--string", OsStr::new("abc"), "std"),
+        data_source("--hex-input", OsStr::new("61 62 63"), "std"),
+        data_source("--base64-input", OsStr::new("YWJj"), "std"),
+    ];
+    let ans = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    for source in &sources {
+        let (digest, size) = digest_source(source, &opts).map_err(|e| e.to_string())?;
+
+        if digest != ans.as_bytes() || size != 3 {
+            return Err(format!(
+                "{}: got {} for {size} bytes",
+                String::from_utf8_lossy(source.name()),
+                String::from_utf8_lossy(&digest)
+            ));
+        }
+    }
+
+    // Every mode gives the same result for data as for a file with the same contents
+    let path = env::temp_dir().join(format!("sha256-data-{}", process::id()));
+    std::fs::write(&path, b"abc").map_err(|e| e.to_string())?;
+
+    for mode in [
+        Mode::Double,
+        Mode::GitBlob,
+        Mode::TreeHash(TreeHash::Glacier),
+        Mode::S3Multipart(2),
+    ] {
+        opts.mode = mode;
+        let from_data = digest_source(&sources[0], &opts).map(|(digest, _)| digest);
+        let from_file =
+            digest_source(&Source::File(path.as_os_str()), &opts).map(|(digest, _)| digest);
+
+        if from_data.map_err(|e| e.to_string()) != from_file.map_err(|e| e.to_string()) {
+            let _ = std::fs::remove_file(&path);
+            return Err(format!("{} differs from the file", algorithm_name(mode)));
+        }
+    }
+
+    std::fs::remove_file(&path).map_err(|e| e.to_string())
+}
+
+#[cfg(unix)]
+#[test]
+fn should_count_piped_bytes() -> Result<(), String> {
+    let opts = options(Mode::Sha256);
+
+    // A pipe's metadata gives its size as 0, so only counting what is read gives the right total and throughput
+    let data: Vec<u8> = (0..3_000_000u32)
+        .map(|i| (i * 31 + i / 251) as u8)
+        .collect();
+    let (digest, size) = with_pipe(&data, |path| {
+        hash_source(&Source::File(path.as_os_str()), &opts)
+    })
+    .and_then(|result| result)
+    .map_err(|e| e.to_string())?;
+
+    if *digest.digest() != Sha256::digest(&data) || size != data.len() as u64 {
+        return Err(format!("got {} for {size} bytes", digest.digest()));
+    }
+
+    let mut stats = Stats::default();
+    stats.add(size);
+    let mut summary = Vec::new();
+    stats.write(&mut summary, 2_000_000);
+    let ans = "1 file, 3000000 bytes in 2.000 s (1.5 MB/s)\n";
+
+    if summary != ans.as_bytes() {
+        return Err(format!("got {:?}", String::from_utf8_lossy(&summary)));
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn should_hash_piped_git_blob() -> Result<(), String> {
+    let opts = options(Mode::GitBlob);
+
+    // `printf 'hello\n' | git hash-object --object-format=sha256 --stdin`
+    let ans = "2cf8d83d9ee29543b34a87727421fdecb7e3f3a183d337639025de576db9ebb4";
+    let (digest, size) = with_pipe(b"hello\n", |path| {
+        digest_source(&Source::File(path.as_os_str()), &opts)
+    })
+    .and_then(|result| result)
+    .map_err(|e| e.to_string())?;
+
+    if digest != ans.as_bytes() || size != 6 {
+        return Err(format!(
+            "got {} for {size} bytes",
+            String::from_utf8_lossy(&digest)
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn should_hash_stdin_for_dash() -> Result<(), String> {
+    use std::os::fd::AsRawFd;
+
+    let (reader, mut writer) = io::pipe().map_err(|e| e.to_string())?;
+
+    // No other test reads stdin, so it can be replaced by the pipe
+    if unsafe { libc::dup2(reader.as_raw_fd(), libc::STDIN_FILENO) } < 0 {
+        return Err(io::Error::last_os_error().to_string());
+    }
+
+    writer.write_all(b"abc").map_err(|e| e.to_string())?;
+    drop(writer);
+
+    let ans = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    let (digest, size) = digest_source(&Source::File(OsStr::new("-")), &options(Mode::Sha256))
+        .map_err(|e| e.to_string())?;
+
+    if digest != ans.as_bytes() || size != 3 {
+        return Err(format!(
+            "got {} for {size} bytes",
+            String::from_utf8_lossy(&digest)
+        ));
+    }
+
+    Ok(())
+}
@@ -1,164 +1,628 @@
-use sha256::{sha256::*, wasi::*};
+use sha256::{encoding::*, manifest, report::*, sha256::*, text::NumBuf, wasi::*};
+use std::process::ExitCode;
 
-#[global_allocator]
-static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+// Natively, the WASI imports only exist in the in-memory host that the tests run against
+#[cfg(not(any(target_arch = "wasm32", feature = "fake-host")))]
+compile_error!("the wasi binary runs under a WASI host: build it with `./build.sh wasi`");
 
-const MAX_SIZE: u64 = 4 * 1024 * 1024 * 1024; // 4 Gb file size limit
-const CHUNK_SIZE: usize = 2 * 1024 * 1024; // 2 Mb chunk size
-const MSG_BLKS_PER_CHUNK: usize = CHUNK_SIZE >> 6;
+const CHUNK_SIZE: usize = 2 * 1024 * 1024; // 2 Mb read buffer
+const CLOCK_MONOTONIC: u32 = 1;
 
 static LINE_FEED: [u8; 1] = [0x0A];
-static SPACES: &[u8; 2] = b"  ";
-static HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
-static ERR_MSG_USAGE: &[u8] = "Usage: sha256 <filename>".as_bytes();
-static ERR_MSG_FILE_TOO_LARGE: &[u8] = "Input file too large (>= 4Gb)".as_bytes();
+static STDIN_NAME: &[u8] = b"-";
+static DATA_OPTIONS: [&[u8]; 3] = [b"--string", b"--hex-input", b"--base64-input"];
 
-// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-fn main() -> Result<(), u16> {
-    let mut msg_schedule = Box::new([0u32; 64]);
+#[cfg(feature = "wasi-report")]
+static ERR_MSG_USAGE: &[u8] = "Usage: sha256 [--encoding=ENCODING] [--format=text|json|jsonl|csv] [-z] [-r] [--stats] <input>...\n       \
+                               sha256 --check [-z] [--encoding=ENCODING] [--stats] <manifest>...\n\n\
+                               Each input is a filename, - for stdin, or data given as --string TEXT, --hex-input HEX or --base64-input BASE64"
+    .as_bytes();
+#[cfg(not(feature = "wasi-report"))]
+static ERR_MSG_USAGE: &[u8] = "Usage: sha256 [--encoding=ENCODING] [-z] [-r] <input>...\n       \
+                               sha256 --check [-z] [--encoding=ENCODING] <manifest>...\n\n\
+                               Each input is a filename, - for stdin, or data given as --string TEXT, --hex-input HEX or --base64-input BASE64"
+    .as_bytes();
+static ERR_PREFIX: &[u8] = "sha256: ".as_bytes();
+static ERR_SEPARATOR: &[u8] = ": ".as_bytes();
+static ERR_MSG_CMD_ARGS: &[u8] = "unable to read command line arguments: ".as_bytes();
+static ERR_MSG_WRITE: &[u8] = "write error: ".as_bytes();
+static ERR_MSG_PREOPENS: &[u8] = "unable to list preopened directories: ".as_bytes();
+static ERR_MSG_NO_LINES: &[u8] = ": no properly formatted checksum lines found\n".as_bytes();
+static ERR_MSG_INVALID_HEX: &[u8] = ": not valid hex\n".as_bytes();
+static ERR_MSG_INVALID_BASE64: &[u8] = ": not valid base64\n".as_bytes();
+
+static CHECK_OK: &[u8] = ": OK\n".as_bytes();
+static CHECK_FAILED: &[u8] = ": FAILED\n".as_bytes();
+static CHECK_FAILED_READ: &[u8] = ": FAILED open or read\n".as_bytes();
+
+static WARN_PREFIX: &[u8] = "sha256: WARNING: ".as_bytes();
+static WARN_MALFORMED: [&[u8]; 2] = [
+    b" line is improperly formatted\n",
+    b" lines are improperly formatted\n",
+];
+static WARN_UNREADABLE: [&[u8]; 2] = [
+    b" listed file could not be read\n",
+    b" listed files could not be read\n",
+];
+static WARN_MISMATCHED: [&[u8]; 2] = [
+    b" computed checksum did NOT match\n",
+    b" computed checksums did NOT match\n",
+];
+
+struct Options {
+    encoding: Encoding,
+    format: Format,
+    zero: bool,
+    recursive: bool,
+    stats: bool,
+}
+
+impl Options {
+    /// Without the `wasi-report` feature, the output is always text.  Reading the format through here lets the
+    /// compiler leave the JSON and CSV writers out of the module
+    fn format(&self) -> Format {
+        if cfg!(feature = "wasi-report") {
+            self.format
+        } else {
+            Format::Text
+        }
+    }
 
-    // The hash values are initialised using the first 32 bits of the fractional part of the square roots of the first
-    // 8 prime numbers (2..19)
-    let mut hash_vals: [u32; 8] = [
-        0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB,
-        0x5BE0CD19,
-    ];
+    fn stats(&self) -> bool {
+        cfg!(feature = "wasi-report") && self.stats
+    }
+}
 
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Every error is reported on stderr as it happens, so main only needs to return the exit status
+fn main() -> ExitCode {
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Get command line args
-    let mut buf = [0u8; 256]; // buffer for cmd line args
-    let mut args: [&str; 4] = [""; 4]; // slices into the buffer
-    let argc = unsafe {
-        match wasi_args_get(&mut buf, &mut args) {
-            Ok(argc) => argc,
-            Err(_) => return Err(1),
+    let args = match wasi_args_get() {
+        Ok(args) => args,
+        Err(errno) => {
+            write_error(&[ERR_PREFIX, ERR_MSG_CMD_ARGS], errno);
+            return ExitCode::FAILURE;
         }
     };
 
-    if argc != 2 {
-        let _ = unsafe { wasi_fd_write(2, &[ERR_MSG_USAGE]).unwrap() };
-        return Err(1);
-    }
+    // Options may be given either as "--option=value" or "--option value"
+    let mut encoding = Some(Encoding::Hex);
+    let mut format = Some(Format::Text);
+    let mut zero = false;
+    let mut check = false;
+    let mut recursive = false;
+    let mut stats = false;
+    let mut missing_value = false;
+    let mut sources: Vec<Source> = Vec::new();
 
-    // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-    // Open file
-    let filename = args[1];
-    let fd = unsafe {
-        match wasi_path_open(3, filename) {
-            Ok(fd) => fd,
-            Err(_) => return Err(1),
+    let mut arg_iter = args.iter().skip(1);
+    while let Some(arg) = arg_iter.next() {
+        // Data may be given as either "--string abc" or "--string=abc"
+        let data_value = DATA_OPTIONS
+            .iter()
+            .find_map(|&opt| match arg.strip_prefix(opt)? {
+                b"" => Some((opt, arg_iter.next())),
+                [b'=', value @ ..] => Some((opt, Some(value))),
+                _ => None,
+            });
+
+        if let Some((opt, value)) = data_value {
+            // Data that cannot be decoded has already been reported
+            match value.map(|value| data_source(opt, value)) {
+                Some(Some(source)) => sources.push(source),
+                Some(None) => return ExitCode::FAILURE,
+                None => missing_value = true,
+            }
+        } else if arg == b"--encoding" {
+            encoding = arg_iter
+                .next()
+                .and_then(option_value)
+                .and_then(Encoding::from_name);
+        } else if arg == b"--format" {
+            format = arg_iter
+                .next()
+                .and_then(option_value)
+                .and_then(Format::from_name);
+        } else if let Some(value) = arg.strip_prefix(b"--encoding=") {
+            encoding = option_value(value).and_then(Encoding::from_name);
+        } else if let Some(value) = arg.strip_prefix(b"--format=") {
+            format = option_value(value).and_then(Format::from_name);
+        } else if arg == b"-z" || arg == b"--zero" {
+            zero = true;
+        } else if arg == b"-c" || arg == b"--check" {
+            check = true;
+        } else if arg == b"-r" || arg == b"--recursive" {
+            recursive = true;
+        } else if arg == b"--stats" {
+            stats = true;
+        } else {
+            sources.push(Source::Path(arg));
         }
+    }
+
+    // Zero-terminated and check modes only apply to text manifests, which cannot hold raw digests.
+    // A manifest lists files, so there is nothing to recurse into, and can only be read from a file
+    let manifests: Vec<&[u8]> = sources
+        .iter()
+        .filter_map(|source| match source {
+            Source::Path(path) => Some(*path),
+            Source::Data { .. } => None,
+        })
+        .collect();
+
+    let (Some(encoding), Some(format), false) = (
+        encoding,
+        format,
+        sources.is_empty()
+            || missing_value
+            || ((zero || check) && format != Some(Format::Text))
+            || (check && (encoding == Some(Encoding::Raw) || recursive))
+            || (check && manifests.len() < sources.len())
+            || (!cfg!(feature = "wasi-report") && (stats || format != Some(Format::Text))),
+    ) else {
+        let _ = wasi_fd_write(Fd::STDERR, &[ERR_MSG_USAGE, &LINE_FEED]);
+        return ExitCode::FAILURE;
+    };
+
+    let opts = Options {
+        encoding,
+        format,
+        zero,
+        recursive,
+        stats,
     };
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-    // Check file size is < 4Gb
-    let file_size = unsafe {
-        match fetch_file_size(fd) {
-            Ok(bytes) => bytes,
-            Err(_) => return Err(1),
+    // Find the directories the host has made available
+    let preopens = match Preopens::discover() {
+        Ok(preopens) => preopens,
+        Err(errno) => {
+            write_error(&[ERR_PREFIX, ERR_MSG_PREOPENS], errno);
+            return ExitCode::FAILURE;
         }
     };
 
-    if file_size >= MAX_SIZE {
-        let _ = unsafe { wasi_fd_write(2, &[ERR_MSG_FILE_TOO_LARGE]).unwrap() };
-        return Err(1);
+    // Allocate the read buffer once, directly on the heap
+    let mut buffer: Box<[u8]> = vec![0u8; CHUNK_SIZE].into_boxed_slice();
+
+    let mut stats = Stats::default();
+    let (all_ok, elapsed_us) = timed(|| {
+        if check {
+            check_manifests(&preopens, &manifests, &opts, &mut buffer, &mut stats)
+        } else {
+            hash_sources(&preopens, &sources, &opts, &mut buffer, &mut stats)
+        }
+    });
+
+    // The summary goes to stderr so that stdout remains a manifest
+    if opts.stats() {
+        let mut out = Vec::new();
+        stats.write(&mut out, elapsed_us);
+        let _ = wasi_fd_write(Fd::STDERR, &[ERR_PREFIX, &out]);
     }
 
-    let mut bytes_remaining = file_size;
-    let file_size_bits = (file_size << 3).to_be_bytes();
+    if all_ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
 
-    // Prepare empty msg block for edge case where file size is an exact integer-multiple of the buffer size
-    let mut empty_msg_blk = [0u8; 64];
-    empty_msg_blk[0] = 0x80;
-    empty_msg_blk[56..].copy_from_slice(&file_size_bits);
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Write one record per input to stdout.  With `-r`, every file beneath a directory is hashed in sorted order.
+/// Returns false if any file could not be hashed
+fn hash_sources(
+    preopens: &Preopens,
+    sources: &[Source],
+    opts: &Options,
+    buffer: &mut [u8],
+    stats: &mut Stats,
+) -> bool {
+    let mut out = RecordWriter::new(opts, stats);
 
-    // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-    // Open file
-    let file_fd = match unsafe { wasi_path_open(3, filename) } {
-        Ok(fd) => fd,
-        Err(_) => return Err(1),
-    };
+    for source in sources {
+        let path = match source {
+            Source::Path(path) => *path,
+            Source::Data { name, data } => {
+                let (result, elapsed_us) = timed(|| Ok((Sha256::digest(data), data.len() as u64)));
 
-    // Allocate buffer directly on the heap
-    let mut buffer: Box<[u8]> = vec![0u8; CHUNK_SIZE].into_boxed_slice();
+                if !out.write(name, result, elapsed_us) {
+                    return false;
+                }
 
-    // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-    // Read file in 2Mb chunks
-    loop {
-        let mut extra_blk = false;
-        let bytes_read = match unsafe { wasi_fd_read(file_fd, &mut buffer) } {
-            Ok(bytes_read) => bytes_read,
-            Err(_e) => return Err(1),
+                continue;
+            }
         };
 
-        if bytes_read == 0 {
-            break; // EOF
+        let walk = if opts.recursive {
+            open_walk(preopens, path)
+        } else {
+            None
+        };
+
+        let written = match walk {
+            None => {
+                let (result, elapsed_us) = timed(|| hash_path(preopens, path, buffer));
+                out.write(path, result, elapsed_us)
+            }
+            Some(Err(errno)) => out.write(path, Err(errno), 0),
+            Some(Ok(mut walk)) => walk.all(|entry| match entry {
+                Ok(entry) => {
+                    let (result, elapsed_us) = timed(|| {
+                        let (input, _) = open_file(entry.dir_fd, entry.name())?;
+                        hash_input(&input, buffer)
+                    });
+                    out.write(&entry.path, result, elapsed_us)
+                }
+                Err(err) => out.write(&err.path, Err(err.errno), 0),
+            }),
+        };
+
+        if !written {
+            return false;
         }
+    }
+
+    out.finish()
+}
 
-        bytes_remaining = bytes_remaining.saturating_sub(bytes_read as u64);
+/// Start walking the path if it is a directory.
+/// Anything that cannot be opened as a directory is hashed as a file instead, which reports any error
+fn open_walk(preopens: &Preopens, path: &[u8]) -> Option<Result<Walk, Errno>> {
+    if path == STDIN_NAME {
+        return None;
+    }
 
-        // Assume we've just read a full chunk
-        let mut msg_blk_count = MSG_BLKS_PER_CHUNK;
+    let (dir_fd, rel_path) = preopens.resolve(path)?;
+    let dir = wasi_path_open_dir(dir_fd, rel_path).ok()?;
 
-        if bytes_read == CHUNK_SIZE {
-            // Check for edge case
-            if bytes_remaining == 0 {
-                extra_blk = true; // file size is exact an integer-multiple of the chunk size
-            }
+    Some(Walk::new(dir, path))
+}
+
+/// Call `f`, returning its result and how long it took in microseconds
+fn timed<T>(f: impl FnOnce() -> T) -> (T, u64) {
+    let start = wasi_clock_time_get(CLOCK_MONOTONIC).unwrap_or(0);
+    let result = f();
+    let end = wasi_clock_time_get(CLOCK_MONOTONIC).unwrap_or(0);
+
+    (result, end.saturating_sub(start) / 1000)
+}
+
+/// Formats each record and writes it to stdout as soon as it is complete
+struct RecordWriter<'a> {
+    opts: &'a Options,
+    stats: &'a mut Stats,
+    encoding: Encoding,
+    out: Vec<u8>,
+    hash_buf: [u8; MAX_ENCODED_LEN],
+    count: usize,
+    all_ok: bool,
+}
+
+impl<'a> RecordWriter<'a> {
+    fn new(opts: &'a Options, stats: &'a mut Stats) -> RecordWriter<'a> {
+        let mut out = Vec::new();
+        opts.format().write_header(&mut out);
+
+        // Raw bytes cannot be embedded in structured output
+        let encoding = if opts.encoding == Encoding::Raw && opts.format() != Format::Text {
+            Encoding::Hex
         } else {
-            // Final partial chunk.
-            // This will always contain at least one empty byte for the EOD marker
-            let eod = bytes_read;
-            buffer[eod] = 0x80;
-            let used = eod + 1;
-
-            // Check that writing the 8-byte file length will not cause buffer overflow
-            if CHUNK_SIZE - used < 8 {
-                extra_blk = true;
-                empty_msg_blk[0] = 0x00; // EOD marker already exists at the end of the previous block
-                buffer[used..].fill(0);
-            } else {
-                let rem = used % 64;
-                let pad_zeros = if rem <= 56 { 56 - rem } else { 56 + 64 - rem };
-                let end = used + pad_zeros + 8; // total bytes to process from this chunk
-
-                buffer[used..used + pad_zeros].fill(0);
-                buffer[end - 8..end].copy_from_slice(&file_size_bits);
-
-                msg_blk_count = end / 64;
+            opts.encoding
+        };
+
+        RecordWriter {
+            opts,
+            stats,
+            encoding,
+            out,
+            hash_buf: [0u8; MAX_ENCODED_LEN],
+            count: 0,
+            all_ok: true,
+        }
+    }
+
+    /// Write the record for one file.  Returns false if stdout could not be written
+    fn write(
+        &mut self,
+        path: &[u8],
+        result: Result<(Digest, u64), Errno>,
+        elapsed_us: u64,
+    ) -> bool {
+        let (opts, out) = (self.opts, &mut self.out);
+
+        if let Ok((_, size)) = result {
+            self.stats.add(size);
+        }
+
+        let mut rec = Record {
+            path,
+            size: None,
+            algorithm: "sha256",
+            digest: None,
+            elapsed_us,
+            error: None,
+        };
+
+        match result {
+            // Raw digests are written on their own so they can be piped straight into other tools
+            Ok((digest, _)) if self.encoding == Encoding::Raw => {
+                out.extend_from_slice(digest.as_bytes());
+            }
+            Ok((digest, _)) if opts.format() == Format::Text => {
+                let len = self.encoding.encode_into(&digest, &mut self.hash_buf);
+                manifest::write_entry(out, &self.hash_buf[..len], path, opts.zero);
+            }
+            Ok((digest, size)) => {
+                let len = self.encoding.encode_into(&digest, &mut self.hash_buf);
+                rec.digest = Some(&self.hash_buf[..len]);
+                rec.size = Some(size);
+                opts.format().write_record(out, self.count, &rec);
+            }
+            Err(errno) => {
+                self.all_ok = false;
+                rec.error = Some(errno.message().as_bytes());
+
+                if opts.format() == Format::Text {
+                    write_path_error(path, errno);
+                }
+
+                opts.format().write_record(out, self.count, &rec);
             }
         }
 
-        // Process available message blocks
-        for blk in 0..msg_blk_count {
-            let blk_idx = blk << 6;
-            phase_1(&buffer[blk_idx..blk_idx + 64], &mut msg_schedule);
-            phase_2(&msg_schedule, &mut hash_vals);
+        self.count += 1;
+        let written = write_stdout(&[out]);
+        out.clear();
+
+        written
+    }
+
+    /// Close the output.  Returns false if any file failed or stdout could not be written
+    fn finish(mut self) -> bool {
+        self.opts.format().write_footer(&mut self.out, self.count);
+
+        write_stdout(&[&self.out]) && self.all_ok
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Verify every entry in the given manifests, printing `<path>: OK` or `<path>: FAILED` for each one.
+/// Returns false if any file failed to verify
+fn check_manifests(
+    preopens: &Preopens,
+    manifests: &[&[u8]],
+    opts: &Options,
+    buffer: &mut [u8],
+    stats: &mut Stats,
+) -> bool {
+    let mut all_ok = true;
+    let mut name: Vec<u8> = Vec::new();
+
+    for &manifest_path in manifests {
+        let contents = match read_path(preopens, manifest_path, buffer) {
+            Ok(contents) => contents,
+            Err(errno) => {
+                write_path_error(manifest_path, errno);
+                all_ok = false;
+                continue;
+            }
+        };
+
+        let (mut checked, mut malformed, mut unreadable, mut mismatched) = (0, 0, 0, 0);
+
+        for line in contents.split(|&b| b == manifest::line_terminator(opts.zero)) {
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some(entry) = manifest::parse_line(line, opts.zero) else {
+                malformed += 1;
+                continue;
+            };
+
+            checked += 1;
+            name.clear();
+            manifest::write_name(&mut name, &entry.path);
+
+            let result = hash_path(preopens, &entry.path, buffer);
+
+            if let Ok((_, size)) = result {
+                stats.add(size);
+            }
+
+            let status = match result {
+                Ok((digest, _)) if opts.encoding.decode(entry.digest) == Some(digest) => CHECK_OK,
+                Ok(_) => {
+                    mismatched += 1;
+                    CHECK_FAILED
+                }
+                Err(errno) => {
+                    write_path_error(&entry.path, errno);
+                    unreadable += 1;
+                    CHECK_FAILED_READ
+                }
+            };
+
+            if !write_stdout(&[&name, status]) {
+                return false;
+            }
         }
 
-        // Potential extra message block for edge case
-        if extra_blk {
-            phase_1(&empty_msg_blk, &mut msg_schedule);
-            phase_2(&msg_schedule, &mut hash_vals);
+        if checked == 0 {
+            name.clear();
+            manifest::write_name(&mut name, manifest_path);
+            let _ = wasi_fd_write(Fd::STDERR, &[ERR_PREFIX, &name, ERR_MSG_NO_LINES]);
+            all_ok = false;
+            continue;
         }
+
+        warn(malformed, WARN_MALFORMED);
+        warn(unreadable, WARN_UNREADABLE);
+        warn(mismatched, WARN_MISMATCHED);
+        all_ok &= unreadable == 0 && mismatched == 0;
     }
 
-    // Convert the hex digits in the hash values to ASCII
-    let mut hash_buf = [0u8; 64];
+    all_ok
+}
+
+fn warn(count: u64, msgs: [&[u8]; 2]) {
+    if count > 0 {
+        let msg = if count == 1 { msgs[0] } else { msgs[1] };
+        let _ = wasi_fd_write(
+            Fd::STDERR,
+            &[WARN_PREFIX, NumBuf::decimal(count).as_bytes(), msg],
+        );
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Something to hash: a path, or data given on the command line
+enum Source<'a> {
+    Path(&'a [u8]),
+    /// Named after the option that gave it, e.g. `--hex-input=616263`, so that it cannot be mistaken for a file
+    Data {
+        name: Vec<u8>,
+        data: Vec<u8>,
+    },
+}
+
+/// The data given by `--string`, `--hex-input` or `--base64-input`.
+/// Whitespace is ignored in hex and base64, so a hex dump can be pasted in as it is.  Returns `None` after reporting
+/// a value that cannot be decoded
+fn data_source(opt: &[u8], value: &[u8]) -> Option<Source<'static>> {
+    let packed: Vec<u8> = value
+        .iter()
+        .copied()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
 
-    for (i, &val) in hash_vals.iter().enumerate() {
-        let offset = i << 3;
-        let bytes = val.to_be_bytes();
+    let (data, err_msg) = match opt {
+        b"--hex-input" => (hex_decode(&packed), ERR_MSG_INVALID_HEX),
+        b"--base64-input" => (base64_decode(&packed), ERR_MSG_INVALID_BASE64),
+        _ => (Some(value.to_vec()), &[][..]),
+    };
 
-        for j in 0..4 {
-            hash_buf[offset + j * 2] = HEX_CHARS[(bytes[j] >> 4) as usize];
-            hash_buf[offset + j * 2 + 1] = HEX_CHARS[(bytes[j] & 0x0F) as usize];
+    let name = [opt, b"=", value].concat();
+
+    match data {
+        Some(data) => Some(Source::Data { name, data }),
+        None => {
+            let mut escaped = Vec::new();
+            manifest::write_name(&mut escaped, &name);
+            let _ = wasi_fd_write(Fd::STDERR, &[ERR_PREFIX, &escaped, err_msg]);
+            None
         }
     }
+}
 
-    let write_buf: [&[u8]; 4] = [&hash_buf, SPACES, filename.as_bytes(), &LINE_FEED];
+/// Option values are ASCII names, so anything that is not UTF-8 is simply unrecognised
+fn option_value(value: &[u8]) -> Option<&str> {
+    str::from_utf8(value).ok()
+}
 
-    let _ = unsafe { wasi_fd_write(1, &write_buf).unwrap() };
+/// Report an error for a path, escaping the path so the message stays on one line
+fn write_path_error(path: &[u8], errno: Errno) {
+    let mut name = Vec::new();
+    manifest::write_name(&mut name, path);
+    write_error(&[ERR_PREFIX, &name, ERR_SEPARATOR], errno);
+}
 
-    Ok(())
+fn write_stdout(bufs: &[&[u8]]) -> bool {
+    match wasi_fd_write(Fd::STDOUT, bufs) {
+        Ok(_) => true,
+        Err(errno) => {
+            write_error(&[ERR_PREFIX, ERR_MSG_WRITE], errno);
+            false
+        }
+    }
 }
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// An open input.  Stdin is only borrowed, but a file is closed as soon as it has been read
+enum Input {
+    Stdin,
+    File(OwnedFd),
+}
+
+impl Input {
+    fn fd(&self) -> Fd {
+        match self {
+            Input::Stdin => Fd::STDIN,
+            Input::File(file) => file.fd(),
+        }
+    }
+}
+
+/// Open a path relative to the preopened directory containing it, or stdin for `-`.
+/// Also returns the file size, or 0 for stdin, whose size is not known in advance
+fn open_path(preopens: &Preopens, path: &[u8]) -> Result<(Input, u64), Errno> {
+    if path == STDIN_NAME {
+        return Ok((Input::Stdin, 0));
+    }
+
+    let (dir_fd, rel_path) = preopens.resolve(path).ok_or(Errno::NotCapable)?;
+    open_file(dir_fd, rel_path)
+}
+
+/// Open a file relative to a directory, returning it with its size
+fn open_file(dir_fd: Fd, rel_path: &[u8]) -> Result<(Input, u64), Errno> {
+    let file = wasi_path_open(dir_fd, rel_path)?;
+    let stat = wasi_fd_filestat_get(file.fd())?;
+
+    // A directory can be opened, but not read
+    if stat.filetype == Filetype::Directory {
+        return Err(Errno::IsDir);
+    }
+
+    Ok((Input::File(file), stat.size))
+}
+
+/// Hash everything that can be read from the path, returning the digest and the number of bytes hashed
+fn hash_path(preopens: &Preopens, path: &[u8], buffer: &mut [u8]) -> Result<(Digest, u64), Errno> {
+    let (input, _) = open_path(preopens, path)?;
+    hash_input(&input, buffer)
+}
+
+fn hash_input(input: &Input, buffer: &mut [u8]) -> Result<(Digest, u64), Errno> {
+    let mut hasher = Sha256::new();
+    let mut size: u64 = 0;
+
+    loop {
+        let bytes_read = match wasi_fd_read(input.fd(), buffer) {
+            Ok(0) => break, // EOF
+            Ok(n) => n,
+            Err(Errno::Intr) => continue,
+            Err(errno) => return Err(errno),
+        };
+
+        hasher.update(&buffer[..bytes_read]);
+        size += bytes_read as u64;
+    }
+
+    Ok((hasher.finalize(), size))
+}
+
+/// Read the whole of a manifest
+fn read_path(preopens: &Preopens, path: &[u8], buffer: &mut [u8]) -> Result<Vec<u8>, Errno> {
+    let (input, size) = open_path(preopens, path)?;
+    let mut contents = Vec::with_capacity(size as usize);
+
+    loop {
+        let bytes_read = match wasi_fd_read(input.fd(), buffer) {
+            Ok(0) => break, // EOF
+            Ok(n) => n,
+            Err(Errno::Intr) => continue,
+            Err(errno) => return Err(errno),
+        };
+
+        contents.extend_from_slice(&buffer[..bytes_read]);
+    }
+
+    Ok(contents)
+}
+
+#[cfg(test)]
+#[path = "wasi/unit_tests.rs"]
+mod unit_tests;
@@ -0,0 +1,183 @@
+use super::*;
+use sha256::wasi::fake::FakeHost;
+use std::process::Command;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// build.sh holds the size budget and fails if the optimized module is over it
+#[test]
+fn should_fit_size_budget() -> Result<(), String> {
+    let output = Command::new("sh")
+        .args(["build.sh", "wasi"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .map_err(|e| format!("unable to run build.sh: {e}"))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Long enough to need many reads, and not a multiple of the block size
+fn test_data() -> Vec<u8> {
+    (0..100_037u32).map(|i| (i * 31 + i / 251) as u8).collect()
+}
+
+#[test]
+fn should_hash_files_despite_random_read_lengths() -> Result<(), String> {
+    let data = test_data();
+    let expected = Sha256::digest(&data);
+
+    for seed in 1..=20 {
+        FakeHost::new()
+            .preopen(b"/", b"")
+            .file(b"data.bin", &data)
+            .random_reads(4096, seed)
+            .install();
+
+        let preopens = Preopens::discover().map_err(|e| format!("{e}"))?;
+        let mut buffer = [0u8; 4096];
+        let (digest, size) =
+            hash_path(&preopens, b"/data.bin", &mut buffer).map_err(|e| format!("{e}"))?;
+
+        if digest != expected || size != data.len() as u64 {
+            return Err(format!("seed {seed}: got {digest} for {size} bytes"));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_hash_stdin_one_byte_at_a_time() -> Result<(), String> {
+    let data = test_data();
+    FakeHost::new().stdin(&data).short_reads(1).install();
+
+    let mut buffer = [0u8; 4096];
+    let (digest, size) =
+        hash_path(&Preopens::default(), b"-", &mut buffer).map_err(|e| format!("{e}"))?;
+
+    if digest != Sha256::digest(&data) || size != data.len() as u64 {
+        return Err(format!("got {digest} for {size} bytes"));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_summarise_run_with_stats() -> Result<(), String> {
+    FakeHost::new()
+        .arg(b"sha256")
+        .arg(b"--stats")
+        .arg(b"/a.txt")
+        .arg(b"/missing.txt")
+        .preopen(b"/", b"")
+        .file(b"a.txt", &[b'a'; 1_000_000])
+        .install();
+
+    if main() != ExitCode::FAILURE {
+        return Err("a missing file should fail the run".to_string());
+    }
+
+    // The fake clock advances 1 ms per reading, and each file is timed as well as the whole run
+    let stderr = String::from_utf8(sha256::wasi::fake::take_stderr()).map_err(|e| e.to_string())?;
+    let ans = "sha256: /missing.txt: No such file or directory (errno 44)\nsha256: 1 file, 1000000 bytes in 0.005 s (200.0 MB/s)\n";
+
+    if stderr != ans {
+        return Err(format!("got {stderr:?}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_hash_data_from_command_line() -> Result<(), String> {
+    FakeHost::new()
+        .arg(b"sha256")
+        .arg(b"--string")
+        .arg(b"abc")
+        .arg(b"--hex-input=61 62 63")
+        .arg(b"--base64-input")
+        .arg(b"YWJj")
+        .install();
+
+    if main() != ExitCode::SUCCESS {
+        return Err(format!(
+            "run failed: {}",
+            String::from_utf8_lossy(&sha256::wasi::fake::take_stderr())
+        ));
+    }
+
+    let stdout = String::from_utf8(sha256::wasi::fake::take_stdout()).map_err(|e| e.to_string())?;
+    let ans = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  --string=abc\n\
+               ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  --hex-input=61 62 63\n\
+               ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  --base64-input=YWJj\n";
+
+    if stdout != ans {
+        return Err(format!("got {stdout:?}"));
+    }
+
+    // Data that cannot be decoded stops the run before anything is hashed
+    FakeHost::new()
+        .arg(b"sha256")
+        .arg(b"--string=abc")
+        .arg(b"--hex-input=616")
+        .install();
+
+    if main() != ExitCode::FAILURE || !sha256::wasi::fake::take_stdout().is_empty() {
+        return Err("odd-length hex should fail the run".to_string());
+    }
+
+    let stderr = String::from_utf8(sha256::wasi::fake::take_stderr()).map_err(|e| e.to_string())?;
+
+    if stderr != "sha256: --hex-input=616: not valid hex\n" {
+        return Err(format!("got {stderr:?}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_retry_interrupted_reads() -> Result<(), String> {
+    let data = test_data();
+    FakeHost::new()
+        .preopen(b"/", b"")
+        .file(b"data.bin", &data)
+        .short_reads(1000)
+        .fail("fd_read", Errno::Intr)
+        .fail("fd_read", Errno::Intr)
+        .install();
+
+    let preopens = Preopens::discover().map_err(|e| format!("{e}"))?;
+    let mut buffer = [0u8; 4096];
+    let (digest, size) =
+        hash_path(&preopens, b"/data.bin", &mut buffer).map_err(|e| format!("hash_path: {e}"))?;
+
+    if digest != Sha256::digest(&data) || size != data.len() as u64 {
+        return Err(format!("got {digest} for {size} bytes"));
+    }
+
+    // Manifests are read by a loop of their own
+    FakeHost::new()
+        .preopen(b"/", b"")
+        .file(b"data.bin", &data)
+        .fail("fd_read", Errno::Intr)
+        .install();
+
+    let preopens = Preopens::discover().map_err(|e| format!("{e}"))?;
+    let contents =
+        read_path(&preopens, b"/data.bin", &mut buffer).map_err(|e| format!("read_path: {e}"))?;
+
+    if contents != data {
+        return Err(format!("read_path returned {} bytes", contents.len()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use crate::{
+    sha256::Digest,
+    text::{HEX_CHARS, hex_encode_into},
+};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Text encodings for digests
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+static HEX_CHARS_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+static BASE64_CHARS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+static BASE64URL_CHARS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+static SRI_PREFIX: &[u8] = b"sha256-";
+static OCI_PREFIX: &[u8] = b"sha256:";
+
+/// Multihash function code for SHA2-256 followed by the digest length
+pub static MULTIHASH_PREFIX: [u8; 2] = [0x12, 0x20];
+
+/// Large enough to hold a digest in any encoding (the longest being `sha256:` followed by 64 hex digits)
+pub const MAX_ENCODED_LEN: usize = 71;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Lowercase hex
+    Hex,
+    /// Uppercase hex
+    UpperHex,
+    /// RFC 4648 base64 with padding
+    Base64,
+    /// RFC 4648 URL-safe base64 without padding
+    Base64Url,
+    /// The 32 digest bytes
+    Raw,
+    /// Subresource Integrity: `sha256-<base64>`
+    Sri,
+    /// OCI content descriptor digest: `sha256:<hex>`
+    Oci,
+    /// Multihash: the bytes `0x12 0x20` followed by the digest, displayed in lowercase hex
+    Multihash,
+}
+
+impl Encoding {
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name {
+            "hex" => Some(Encoding::Hex),
+            "HEX" => Some(Encoding::UpperHex),
+            "base64" => Some(Encoding::Base64),
+            "base64url" => Some(Encoding::Base64Url),
+            "raw" => Some(Encoding::Raw),
+            "sri" => Some(Encoding::Sri),
+            "oci" => Some(Encoding::Oci),
+            "multihash" => Some(Encoding::Multihash),
+            _ => None,
+        }
+    }
+
+    /// Write the encoded digest to `out`, which must be at least `MAX_ENCODED_LEN` bytes long.
+    /// Returns the number of bytes written
+    pub fn encode_into(&self, digest: &Digest, out: &mut [u8]) -> usize {
+        let bytes = digest.as_bytes();
+
+        match self {
+            Encoding::Hex => hex_encode_into(bytes, HEX_CHARS, out),
+            Encoding::UpperHex => hex_encode_into(bytes, HEX_CHARS_UPPER, out),
+            Encoding::Base64 => encode_base64_into(bytes, BASE64_CHARS, true, out),
+            Encoding::Base64Url => encode_base64_into(bytes, BASE64URL_CHARS, false, out),
+            Encoding::Raw => {
+                out[..32].copy_from_slice(bytes);
+                32
+            }
+            Encoding::Sri => {
+                out[..SRI_PREFIX.len()].copy_from_slice(SRI_PREFIX);
+                SRI_PREFIX.len()
+                    + encode_base64_into(bytes, BASE64_CHARS, true, &mut out[SRI_PREFIX.len()..])
+            }
+            Encoding::Oci => {
+                out[..OCI_PREFIX.len()].copy_from_slice(OCI_PREFIX);
+                OCI_PREFIX.len() + hex_encode_into(bytes, HEX_CHARS, &mut out[OCI_PREFIX.len()..])
+            }
+            Encoding::Multihash => {
+                let len = hex_encode_into(&MULTIHASH_PREFIX, HEX_CHARS, out);
+                len + hex_encode_into(bytes, HEX_CHARS, &mut out[len..])
+            }
+        }
+    }
+
+    pub fn encode(&self, digest: &Digest) -> Vec<u8> {
+        let mut out = [0u8; MAX_ENCODED_LEN];
+        let len = self.encode_into(digest, &mut out);
+        out[..len].to_vec()
+    }
+
+    /// Parse a digest written in this encoding
+    pub fn decode(&self, text: &[u8]) -> Option<Digest> {
+        let bytes = match self {
+            Encoding::Hex | Encoding::UpperHex => hex_decode(text)?,
+            Encoding::Base64 => decode_base64(text, BASE64_CHARS)?,
+            Encoding::Base64Url => decode_base64(text, BASE64URL_CHARS)?,
+            Encoding::Raw => text.to_vec(),
+            Encoding::Sri => decode_base64(text.strip_prefix(SRI_PREFIX)?, BASE64_CHARS)?,
+            Encoding::Oci => hex_decode(text.strip_prefix(OCI_PREFIX)?)?,
+            Encoding::Multihash => {
+                let bytes = hex_decode(text)?;
+                bytes.strip_prefix(&MULTIHASH_PREFIX)?.to_vec()
+            }
+        };
+
+        Some(Digest(bytes.try_into().ok()?))
+    }
+
+    /// Recognise a digest written in any text encoding, returning the encoding and the digest.
+    /// The encodings differ in length or prefix, except that base64 without `+`, `/` or `_`, `-` is also valid URL-safe
+    /// base64: it is reported as `Base64`.  Hex containing only uppercase letters is reported as `UpperHex`
+    pub fn detect(text: &[u8]) -> Option<(Encoding, Digest)> {
+        let encodings = [
+            Encoding::Sri,
+            Encoding::Oci,
+            Encoding::Hex,
+            Encoding::Multihash,
+            Encoding::Base64,
+            Encoding::Base64Url,
+        ];
+
+        let (encoding, digest) = encodings
+            .into_iter()
+            .find_map(|encoding| Some((encoding, encoding.decode(text)?)))?;
+
+        let upper =
+            text.iter().any(u8::is_ascii_uppercase) && !text.iter().any(u8::is_ascii_lowercase);
+
+        if encoding == Encoding::Hex && upper {
+            Some((Encoding::UpperHex, digest))
+        } else {
+            Some((encoding, digest))
+        }
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+fn hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Hex digits in either case.  Returns `None` for an odd number of digits or any other character
+pub fn hex_decode(text: &[u8]) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    text.chunks_exact(2)
+        .map(|pair| Some(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+        .collect()
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+fn encode_base64_into(bytes: &[u8], chars: &[u8; 64], pad: bool, out: &mut [u8]) -> usize {
+    let mut len = 0;
+
+    for group in bytes.chunks(3) {
+        let b = [
+            group[0],
+            *group.get(1).unwrap_or(&0),
+            *group.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, b[0], b[1], b[2]]);
+
+        for i in 0..4 {
+            if i <= group.len() {
+                out[len] = chars[(n >> (18 - i * 6)) as usize & 0x3F];
+            } else if pad {
+                out[len] = b'=';
+            } else {
+                break;
+            }
+            len += 1;
+        }
+    }
+
+    len
+}
+
+/// Standard (RFC 4648 section 4) base64 with `=` padding, written to `out`.
+/// Returns the number of bytes written
+pub fn base64_encode_into(bytes: &[u8], out: &mut [u8]) -> usize {
+    encode_base64_into(bytes, BASE64_CHARS, true, out)
+}
+
+/// Standard (RFC 4648 section 4) base64 with `=` padding
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = vec![0u8; bytes.len().div_ceil(3) * 4];
+    encode_base64_into(bytes, BASE64_CHARS, true, &mut out);
+    out.into_iter().map(char::from).collect()
+}
+
+/// URL-safe (RFC 4648 section 5) base64 without padding
+pub fn base64url_encode(bytes: &[u8]) -> String {
+    let mut out = vec![0u8; bytes.len().div_ceil(3) * 4];
+    let len = encode_base64_into(bytes, BASE64URL_CHARS, false, &mut out);
+    out[..len].iter().map(|&c| char::from(c)).collect()
+}
+
+/// Base64 in the given alphabet.  Trailing `=` padding is optional
+fn decode_base64(text: &[u8], chars: &[u8; 64]) -> Option<Vec<u8>> {
+    let text = match text.iter().position(|&c| c == b'=') {
+        Some(eod) if text[eod..].iter().all(|&c| c == b'=') && text.len().is_multiple_of(4) => {
+            &text[..eod]
+        }
+        Some(_) => return None,
+        None => text,
+    };
+
+    // A single character left over cannot encode a whole byte
+    if text.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+
+    for group in text.chunks(4) {
+        let mut n: u32 = 0;
+
+        for (i, &c) in group.iter().enumerate() {
+            let sextet = chars.iter().position(|&b| b == c)? as u32;
+            n |= sextet << (18 - i * 6);
+        }
+
+        out.extend_from_slice(&n.to_be_bytes()[1..group.len()]);
+    }
+
+    Some(out)
+}
+
+/// Standard base64, with or without padding
+pub fn base64_decode(text: &[u8]) -> Option<Vec<u8>> {
+    decode_base64(text, BASE64_CHARS)
+}
+
+/// URL-safe base64, with or without padding
+pub fn base64url_decode(text: &[u8]) -> Option<Vec<u8>> {
+    decode_base64(text, BASE64URL_CHARS)
+}
+
+#[cfg(test)]
+mod unit_tests;
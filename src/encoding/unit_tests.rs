@@ -0,0 +1,169 @@
+use super::*;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_encode_base64() -> Result<(), String> {
+    // RFC 4648 section 10 test vectors
+    let vectors = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    for (input, ans) in vectors {
+        let encoded = base64_encode(input.as_bytes());
+
+        if encoded != ans {
+            return Err(format!(
+                "base64_encode({input:?}) returned {encoded:?}, expected {ans:?}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_decode_base64() -> Result<(), String> {
+    for input in ["", "f", "fo", "foo", "foob", "fooba", "foobar"] {
+        let encoded = base64_encode(input.as_bytes());
+        let unpadded = encoded.trim_end_matches('=');
+
+        for text in [encoded.as_str(), unpadded] {
+            match base64_decode(text.as_bytes()) {
+                Some(bytes) if bytes == input.as_bytes() => {}
+                decoded => return Err(format!("base64_decode({text:?}) returned {decoded:?}")),
+            }
+        }
+    }
+
+    for text in ["Z", "Zm9v!", "Zg=", "Z=g="] {
+        if let Some(bytes) = base64_decode(text.as_bytes()) {
+            return Err(format!(
+                "base64_decode({text:?}) accepted bad input as {bytes:?}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_encode_digest() -> Result<(), String> {
+    let digest = crate::sha256::Sha256::digest(b"abc");
+    let vectors = [
+        (
+            Encoding::Hex,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            Encoding::UpperHex,
+            "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD",
+        ),
+        (
+            Encoding::Base64,
+            "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=",
+        ),
+        (
+            Encoding::Base64Url,
+            "ungWv48Bz-pBQUDeXa4iI7ADYaOWF3qctBD_YfIAFa0",
+        ),
+        (
+            Encoding::Sri,
+            "sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=",
+        ),
+        (
+            Encoding::Oci,
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            Encoding::Multihash,
+            "1220ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+    ];
+
+    for (encoding, ans) in vectors {
+        let encoded = encoding.encode(&digest);
+
+        if encoded != ans.as_bytes() {
+            return Err(format!(
+                "{encoding:?} encoding returned {:?}, expected {ans:?}",
+                String::from_utf8_lossy(&encoded)
+            ));
+        }
+
+        if encoding.decode(ans.as_bytes()) != Some(digest) {
+            return Err(format!("{encoding:?} failed to decode {ans:?}"));
+        }
+    }
+
+    if Encoding::Raw.encode(&digest) != digest.as_bytes() {
+        return Err("Raw encoding did not return the digest bytes".to_string());
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_detect_encoding() -> Result<(), String> {
+    let digest = crate::sha256::Sha256::digest(b"abc");
+
+    for encoding in [
+        Encoding::Hex,
+        Encoding::UpperHex,
+        Encoding::Base64,
+        Encoding::Base64Url,
+        Encoding::Sri,
+        Encoding::Oci,
+        Encoding::Multihash,
+    ] {
+        let encoded = encoding.encode(&digest);
+
+        if Encoding::detect(&encoded) != Some((encoding, digest)) {
+            return Err(format!(
+                "{:?} was not detected as {encoding:?}",
+                String::from_utf8_lossy(&encoded)
+            ));
+        }
+    }
+
+    let vectors: [(&str, Option<Encoding>); 5] = [
+        // Unpadded base64 that only uses characters common to both alphabets
+        (
+            "ungWv48Bz1pBQUDeXa4iI7ADYaOWF3qctBD1YfIAFa0",
+            Some(Encoding::Base64),
+        ),
+        (
+            "Ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            Some(Encoding::Hex),
+        ),
+        (
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015a",
+            None,
+        ),
+        (
+            "sha256-ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            None,
+        ),
+        ("", None),
+    ];
+
+    for (text, ans) in vectors {
+        let detected = Encoding::detect(text.as_bytes()).map(|(encoding, _)| encoding);
+
+        if detected != ans {
+            return Err(format!(
+                "{text:?} was detected as {detected:?}, expected {ans:?}"
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use crate::sha256::*;
+
+use std::io::{self, Read};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Git object IDs for repositories using the SHA256 object format
+//
+// An object ID is the SHA256 of the header "<type> <content length in decimal>\0" followed by the object's content
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectType {
+    Blob,
+    Tree,
+    Commit,
+    Tag,
+}
+
+impl ObjectType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ObjectType::Blob => "blob",
+            ObjectType::Tree => "tree",
+            ObjectType::Commit => "commit",
+            ObjectType::Tag => "tag",
+        }
+    }
+}
+
+fn object_hasher(object_type: ObjectType, len: u64) -> Sha256 {
+    let mut hasher = Sha256::new();
+    hasher.update(object_type.name().as_bytes());
+    hasher.update(b" ");
+    hasher.update(len.to_string().as_bytes());
+    hasher.update(b"\0");
+    hasher
+}
+
+pub fn object_id(object_type: ObjectType, content: &[u8]) -> Digest {
+    let mut hasher = object_hasher(object_type, content.len() as u64);
+    hasher.update(content);
+    hasher.finalize()
+}
+
+pub fn blob_id(content: &[u8]) -> Digest {
+    object_id(ObjectType::Blob, content)
+}
+
+/// Stream a blob of `len` bytes from `reader` (e.g. a file whose size is already known), as
+/// `git hash-object --object-format=sha256` does.
+/// Fails if the reader does not supply exactly `len` bytes
+pub fn blob_id_from_reader(reader: &mut impl Read, len: u64) -> io::Result<Digest> {
+    let mut hasher = object_hasher(ObjectType::Blob, len);
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut bytes_read: u64 = 0;
+
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => {
+                hasher.update(&buffer[..n]);
+                bytes_read += n as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    if bytes_read != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "blob size changed while being hashed",
+        ));
+    }
+
+    Ok(hasher.finalize())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileMode {
+    Regular,
+    Executable,
+    Symlink,
+    Directory,
+    Submodule,
+}
+
+impl FileMode {
+    /// The octal mode as written in tree objects (directories have no leading zero)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileMode::Regular => "100644",
+            FileMode::Executable => "100755",
+            FileMode::Symlink => "120000",
+            FileMode::Directory => "40000",
+            FileMode::Submodule => "160000",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeEntry {
+    pub mode: FileMode,
+    pub name: Vec<u8>,
+    pub id: Digest,
+}
+
+impl TreeEntry {
+    // Git sorts tree entries bytewise by name, except that directory names are compared as though they end in '/'
+    fn sort_key(&self) -> impl Iterator<Item = &u8> {
+        let suffix: &[u8] = if self.mode == FileMode::Directory {
+            b"/"
+        } else {
+            b""
+        };
+        self.name.iter().chain(suffix)
+    }
+}
+
+/// Collects the entries of a single directory and serialises them as a canonical git tree object
+#[derive(Clone, Debug, Default)]
+pub struct TreeBuilder {
+    entries: Vec<TreeEntry>,
+}
+
+impl TreeBuilder {
+    pub fn new() -> TreeBuilder {
+        TreeBuilder::default()
+    }
+
+    /// Add an entry.  The name must be a single, non-empty path component not already present in this tree
+    pub fn add(&mut self, mode: FileMode, name: &[u8], id: Digest) -> Result<(), &'static str> {
+        if name.is_empty()
+            || name == b"."
+            || name == b".."
+            || name.contains(&b'/')
+            || name.contains(&0)
+        {
+            return Err("Invalid tree entry name");
+        }
+
+        if self.entries.iter().any(|e| e.name == name) {
+            return Err("Duplicate tree entry name");
+        }
+
+        self.entries.push(TreeEntry {
+            mode,
+            name: name.to_vec(),
+            id,
+        });
+
+        Ok(())
+    }
+
+    /// The tree object's content: for each entry in canonical order, "<mode> <name>\0" followed by the 32-byte ID
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<&TreeEntry> = self.entries.iter().collect();
+        entries.sort_by(|a, b| a.sort_key().cmp(b.sort_key()));
+
+        let mut bytes = Vec::new();
+
+        for entry in entries {
+            bytes.extend_from_slice(entry.mode.as_str().as_bytes());
+            bytes.push(b' ');
+            bytes.extend_from_slice(&entry.name);
+            bytes.push(0);
+            bytes.extend_from_slice(entry.id.as_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn id(&self) -> Digest {
+        object_id(ObjectType::Tree, &self.to_bytes())
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// The fields of a commit object.
+/// `author` and `committer` are identity lines such as `A U Thor <author@example.com> 1700000000 +0000`
+pub struct Commit<'a> {
+    pub tree: Digest,
+    pub parents: &'a [Digest],
+    pub author: &'a str,
+    pub committer: &'a str,
+    pub message: &'a str,
+}
+
+impl Commit<'_> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut text = format!("tree {}\n", self.tree);
+
+        for parent in self.parents {
+            text.push_str(&format!("parent {parent}\n"));
+        }
+
+        text.push_str(&format!(
+            "author {}\ncommitter {}\n\n{}",
+            self.author, self.committer, self.message
+        ));
+
+        text.into_bytes()
+    }
+
+    pub fn id(&self) -> Digest {
+        object_id(ObjectType::Commit, &self.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod unit_tests;
@@ -0,0 +1,91 @@
+use super::*;
+
+// Object IDs reported by git 2.39 in a repository created with `git init --object-format=sha256`
+static HELLO_BLOB: &str = "2cf8d83d9ee29543b34a87727421fdecb7e3f3a183d337639025de576db9ebb4";
+static ROOT_TREE: &str = "ca34cc884243678018f9708eb9f9536b6d03949103eeaa2550c213e597040497";
+static COMMIT: &str = "07a0b98e1fbcd90e1a05ac89a8b5879b782eb35a4953dbd454e0c30616b89b2a";
+
+fn root_tree() -> Result<TreeBuilder, &'static str> {
+    let mut sub = TreeBuilder::new();
+    sub.add(FileMode::Regular, b"b", blob_id(b"x"))?;
+
+    // Added out of order: "sub.txt" must sort before the directory "sub"
+    let mut root = TreeBuilder::new();
+    root.add(FileMode::Directory, b"sub", sub.id())?;
+    root.add(FileMode::Regular, b"sub.txt", blob_id(b"y"))?;
+    root.add(FileMode::Executable, b"run.sh", blob_id(b"#!/bin/sh\n"))?;
+    root.add(FileMode::Regular, b"a.txt", blob_id(b"hello\n"))?;
+
+    Ok(root)
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_blob() -> Result<(), String> {
+    let id = blob_id(b"hello\n").to_string();
+
+    if id != HELLO_BLOB {
+        return Err(format!("blob_id() returned {id}, expected {HELLO_BLOB}"));
+    }
+
+    let id = blob_id_from_reader(&mut &b"hello\n"[..], 6)
+        .map_err(|e| e.to_string())?
+        .to_string();
+
+    if id != HELLO_BLOB {
+        return Err(format!(
+            "blob_id_from_reader() returned {id}, expected {HELLO_BLOB}"
+        ));
+    }
+
+    if blob_id_from_reader(&mut &b"hello\n"[..], 7).is_ok() {
+        return Err("blob_id_from_reader() accepted a short blob".to_string());
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_tree_in_canonical_order() -> Result<(), String> {
+    let id = root_tree()?.id().to_string();
+
+    if id != ROOT_TREE {
+        return Err(format!(
+            "TreeBuilder::id() returned {id}, expected {ROOT_TREE}"
+        ));
+    }
+
+    let mut tree = TreeBuilder::new();
+    for name in [&b""[..], b"a/b", b"..", b"a\0b"] {
+        if tree.add(FileMode::Regular, name, blob_id(b"")).is_ok() {
+            return Err(format!("TreeBuilder::add() accepted invalid name {name:?}"));
+        }
+    }
+
+    tree.add(FileMode::Regular, b"a", blob_id(b""))?;
+    if tree.add(FileMode::Directory, b"a", blob_id(b"")).is_ok() {
+        return Err("TreeBuilder::add() accepted a duplicate name".to_string());
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_commit() -> Result<(), String> {
+    let commit = Commit {
+        tree: root_tree()?.id(),
+        parents: &[],
+        author: "A <a@b.c> 1700000000 +0000",
+        committer: "A <a@b.c> 1700000000 +0000",
+        message: "Initial commit\n",
+    };
+    let id = commit.id().to_string();
+
+    if id != COMMIT {
+        return Err(format!("Commit::id() returned {id}, expected {COMMIT}"));
+    }
+
+    Ok(())
+}
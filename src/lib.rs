@@ -1,2 +1,27 @@
+// Without the `std` feature only the `sha256` core, the text formatting and the WASI wrappers that need no allocator
+// are built, for WebAssembly modules that have no allocator
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+pub mod aws;
+#[cfg(feature = "std")]
+pub mod encoding;
+#[cfg(feature = "std")]
+pub mod git;
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
+pub mod merkle;
+#[cfg(all(feature = "std", unix))]
+pub mod mmap;
+#[cfg(feature = "std")]
+pub mod report;
 pub mod sha256;
-pub mod wasi;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub mod sha256d;
+#[cfg(feature = "std")]
+pub mod tagged_hash;
+pub mod text;
+#[cfg(feature = "std")]
+pub mod tree_hash;
+pub mod wasi;
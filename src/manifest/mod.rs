@@ -0,0 +1,137 @@
+use std::borrow::Cow;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Checksum manifest lines in the GNU coreutils `sha256sum` format
+//
+// Each line is `<digest>  <path>`.  A path containing a line feed, carriage return or backslash would corrupt the line,
+// so such a line starts with a backslash and those characters are written as `\n`, `\r` and `\\` respectively.
+//
+// In zero-terminated mode each entry ends with a NUL byte instead of a line feed and paths are written exactly as they
+// are, since a NUL byte cannot appear in a path
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+
+/// One parsed manifest line
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry<'a> {
+    /// The digest text, exactly as written
+    pub digest: &'a [u8],
+    /// The path bytes, with any escapes undone
+    pub path: Cow<'a, [u8]>,
+}
+
+pub fn line_terminator(zero: bool) -> u8 {
+    if zero { b'\0' } else { b'\n' }
+}
+
+/// Does the path need escaping to fit on a single line?
+pub fn needs_escape(path: &[u8]) -> bool {
+    path.iter().any(|b| matches!(b, b'\n' | b'\r' | b'\\'))
+}
+
+/// Append the path with line feeds, carriage returns and backslashes escaped.
+/// The caller is responsible for the backslash that starts the line
+pub fn write_escaped(out: &mut Vec<u8>, path: &[u8]) {
+    for &b in path {
+        match b {
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            _ => out.push(b),
+        }
+    }
+}
+
+/// Append the path as it appears in messages such as `<path>: OK`: on a single line, escaped with a leading backslash
+/// when necessary
+pub fn write_name(out: &mut Vec<u8>, path: &[u8]) {
+    if needs_escape(path) {
+        out.push(b'\\');
+        write_escaped(out, path);
+    } else {
+        out.extend_from_slice(path);
+    }
+}
+
+/// Append a complete `<digest>  <path>` entry, including its terminator
+pub fn write_entry(out: &mut Vec<u8>, digest: &[u8], path: &[u8], zero: bool) {
+    let escape = !zero && needs_escape(path);
+
+    if escape {
+        out.push(b'\\');
+    }
+
+    out.extend_from_slice(digest);
+    out.extend_from_slice(b"  ");
+
+    if escape {
+        write_escaped(out, path);
+    } else {
+        out.extend_from_slice(path);
+    }
+
+    out.push(line_terminator(zero));
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Parse one manifest line, without its terminator.
+/// Both the text (`<digest>  <path>`) and binary (`<digest> *<path>`) markers are accepted.
+/// Returns `None` if the line is malformed, including an escaped line containing an unknown escape sequence
+pub fn parse_line(line: &[u8], zero: bool) -> Option<Entry<'_>> {
+    // Tolerate manifests written on Windows
+    let line = if zero {
+        line
+    } else {
+        line.strip_suffix(b"\r").unwrap_or(line)
+    };
+
+    let (escaped, line) = match line.strip_prefix(b"\\") {
+        Some(rest) if !zero => (true, rest),
+        _ => (false, line),
+    };
+
+    let sep = line.iter().position(|&b| b == b' ')?;
+    let (digest, rest) = line.split_at(sep);
+
+    if digest.is_empty() || !matches!(rest.get(1), Some(b' ' | b'*')) {
+        return None;
+    }
+
+    let path = &rest[2..];
+
+    if path.is_empty() {
+        return None;
+    }
+
+    let path = if escaped {
+        Cow::Owned(unescape(path)?)
+    } else {
+        Cow::Borrowed(path)
+    };
+
+    Some(Entry { digest, path })
+}
+
+/// Undo `write_escaped`
+pub fn unescape(path: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(path.len());
+    let mut bytes = path.iter();
+
+    while let Some(&b) = bytes.next() {
+        if b != b'\\' {
+            out.push(b);
+            continue;
+        }
+
+        match bytes.next()? {
+            b'n' => out.push(b'\n'),
+            b'r' => out.push(b'\r'),
+            b'\\' => out.push(b'\\'),
+            _ => return None,
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod unit_tests;
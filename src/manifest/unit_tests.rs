@@ -0,0 +1,88 @@
+use super::*;
+
+static DIGEST: &[u8] = b"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+// Lines as written by GNU coreutils 9.1 `sha256sum`
+static ESCAPED_LINES: [(&[u8], &[u8]); 4] = [
+    (b"abc.txt", b"  abc.txt\n"),
+    (b"new\nline", b"  new\\nline\n"),
+    (b"back\\slash", b"  back\\\\slash\n"),
+    (b"cr\r\\\n", b"  cr\\r\\\\\\n\n"),
+];
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_escape_like_coreutils() -> Result<(), String> {
+    for (path, suffix) in ESCAPED_LINES {
+        let mut ans = Vec::new();
+        if needs_escape(path) {
+            ans.push(b'\\');
+        }
+        ans.extend_from_slice(DIGEST);
+        ans.extend_from_slice(suffix);
+
+        let mut out = Vec::new();
+        write_entry(&mut out, DIGEST, path, false);
+
+        if out != ans {
+            return Err(format!(
+                "write_entry({path:?}) returned {:?}, expected {:?}",
+                String::from_utf8_lossy(&out),
+                String::from_utf8_lossy(&ans)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_round_trip_every_path() -> Result<(), String> {
+    let mut paths: Vec<Vec<u8>> = ESCAPED_LINES.iter().map(|(p, _)| p.to_vec()).collect();
+    paths.push(b"\xFF\xFEnot utf-8".to_vec());
+    paths.push(b"  leading spaces and a trailing \\".to_vec());
+    paths.push((1..=255).collect());
+
+    for zero in [false, true] {
+        for path in &paths {
+            let mut out = Vec::new();
+            write_entry(&mut out, DIGEST, path, zero);
+
+            let line = out.strip_suffix(&[line_terminator(zero)]).unwrap();
+            let entry = parse_line(line, zero);
+            let ans = Entry {
+                digest: DIGEST,
+                path: Cow::Borrowed(path),
+            };
+
+            if entry.as_ref() != Some(&ans) {
+                return Err(format!("parse_line({line:?}, {zero}) returned {entry:?}"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_reject_malformed_lines() -> Result<(), String> {
+    let lines: [&[u8]; 5] = [b"", b"abc", b"abc file", b"abc  ", b"\\abc  bad\\escape"];
+
+    for line in lines {
+        if let Some(entry) = parse_line(line, false) {
+            return Err(format!("parse_line({line:?}) returned {entry:?}"));
+        }
+    }
+
+    let binary = parse_line(b"abc *file\r", false);
+
+    if binary.as_ref().map(|e| &*e.path) != Some(b"file") {
+        return Err(format!(
+            "parse_line() returned {binary:?} for a binary mode line"
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,310 @@
+use crate::sha256::*;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// RFC 6962 / RFC 9162 binary Merkle trees
+//
+// Leaves are hashed as SHA256(0x00 || data) and interior nodes as SHA256(0x01 || left || right), so that a leaf can
+// never be passed off as an interior node.  A tree of n > 1 leaves is split into a left subtree holding the largest
+// power of two strictly less than n leaves and a right subtree holding the rest.
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+static LEAF_PREFIX: [u8; 1] = [0x00];
+static NODE_PREFIX: [u8; 1] = [0x01];
+
+pub fn leaf_hash(data: &[u8]) -> Digest {
+    let mut hasher = Sha256::new();
+    hasher.update(&LEAF_PREFIX);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+pub fn node_hash(left: &Digest, right: &Digest) -> Digest {
+    let mut hasher = Sha256::new();
+    hasher.update(&NODE_PREFIX);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+// The largest power of two strictly less than n (n must be > 1)
+fn split_point(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+/// The Merkle Tree Hash of a list of leaf hashes.  The root of an empty tree is the hash of the empty string
+pub fn root_hash(leaf_hashes: &[Digest]) -> Digest {
+    match leaf_hashes.len() {
+        0 => Sha256::digest(b""),
+        1 => leaf_hashes[0],
+        n => {
+            let k = split_point(n);
+            node_hash(&root_hash(&leaf_hashes[..k]), &root_hash(&leaf_hashes[k..]))
+        }
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// A Merkle tree that retains every leaf hash so that it can generate proofs
+#[derive(Clone, Debug, Default)]
+pub struct MerkleTree {
+    leaf_hashes: Vec<Digest>,
+}
+
+impl MerkleTree {
+    pub fn new() -> MerkleTree {
+        MerkleTree::default()
+    }
+
+    pub fn from_leaves<'a>(leaves: impl IntoIterator<Item = &'a [u8]>) -> MerkleTree {
+        MerkleTree {
+            leaf_hashes: leaves.into_iter().map(leaf_hash).collect(),
+        }
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.leaf_hashes.push(leaf_hash(data));
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaf_hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaf_hashes.is_empty()
+    }
+
+    pub fn leaf_hashes(&self) -> &[Digest] {
+        &self.leaf_hashes
+    }
+
+    pub fn root(&self) -> Digest {
+        root_hash(&self.leaf_hashes)
+    }
+
+    /// The audit path for the leaf at `index` (RFC 6962 section 2.1.1), or `None` if there is no such leaf
+    pub fn inclusion_proof(&self, index: usize) -> Option<Vec<Digest>> {
+        if index >= self.leaf_hashes.len() {
+            return None;
+        }
+
+        let mut proof = Vec::new();
+        inclusion_path(index, &self.leaf_hashes, &mut proof);
+        Some(proof)
+    }
+
+    /// Proof that the first `old_size` leaves of this tree form the tree whose root was previously published
+    /// (RFC 6962 section 2.1.2), or `None` unless 0 < `old_size` <= the current size
+    pub fn consistency_proof(&self, old_size: usize) -> Option<Vec<Digest>> {
+        if old_size == 0 || old_size > self.leaf_hashes.len() {
+            return None;
+        }
+
+        let mut proof = Vec::new();
+        consistency_subproof(old_size, &self.leaf_hashes, true, &mut proof);
+        Some(proof)
+    }
+}
+
+fn inclusion_path(index: usize, leaf_hashes: &[Digest], proof: &mut Vec<Digest>) {
+    let n = leaf_hashes.len();
+
+    if n > 1 {
+        let k = split_point(n);
+
+        if index < k {
+            inclusion_path(index, &leaf_hashes[..k], proof);
+            proof.push(root_hash(&leaf_hashes[k..]));
+        } else {
+            inclusion_path(index - k, &leaf_hashes[k..], proof);
+            proof.push(root_hash(&leaf_hashes[..k]));
+        }
+    }
+}
+
+fn consistency_subproof(
+    m: usize,
+    leaf_hashes: &[Digest],
+    is_complete_subtree: bool,
+    proof: &mut Vec<Digest>,
+) {
+    let n = leaf_hashes.len();
+
+    if m == n {
+        if !is_complete_subtree {
+            proof.push(root_hash(leaf_hashes));
+        }
+        return;
+    }
+
+    let k = split_point(n);
+
+    if m <= k {
+        consistency_subproof(m, &leaf_hashes[..k], is_complete_subtree, proof);
+        proof.push(root_hash(&leaf_hashes[k..]));
+    } else {
+        consistency_subproof(m - k, &leaf_hashes[k..], false, proof);
+        proof.push(root_hash(&leaf_hashes[..k]));
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Proof verification (RFC 9162 sections 2.1.3.2 and 2.1.4.2)
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+
+// Right-shift both indices until the LSB of `fn_` is set or `fn_` is zero
+fn shift_to_right_edge(fn_: &mut u64, sn: &mut u64) {
+    while *fn_ & 1 == 0 && *fn_ != 0 {
+        *fn_ >>= 1;
+        *sn >>= 1;
+    }
+}
+
+pub fn verify_inclusion(
+    leaf_hash: &Digest,
+    index: u64,
+    tree_size: u64,
+    proof: &[Digest],
+    root: &Digest,
+) -> bool {
+    if index >= tree_size {
+        return false;
+    }
+
+    let mut fn_ = index;
+    let mut sn = tree_size - 1;
+    let mut r = *leaf_hash;
+
+    for p in proof {
+        if sn == 0 {
+            return false;
+        }
+
+        if fn_ & 1 == 1 || fn_ == sn {
+            r = node_hash(p, &r);
+            shift_to_right_edge(&mut fn_, &mut sn);
+        } else {
+            r = node_hash(&r, p);
+        }
+
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    sn == 0 && r == *root
+}
+
+pub fn verify_consistency(
+    old_size: u64,
+    new_size: u64,
+    old_root: &Digest,
+    new_root: &Digest,
+    proof: &[Digest],
+) -> bool {
+    if old_size > new_size {
+        return false;
+    }
+
+    if old_size == new_size {
+        return proof.is_empty() && old_root == new_root;
+    }
+
+    // Every tree is consistent with the empty tree
+    if old_size == 0 {
+        return proof.is_empty();
+    }
+
+    // When the old tree is a complete subtree, its root is the implied first element of the proof
+    let mut path = proof.iter();
+    let first = if old_size.is_power_of_two() {
+        *old_root
+    } else {
+        match path.next() {
+            Some(c) => *c,
+            None => return false,
+        }
+    };
+
+    let mut fn_ = old_size - 1;
+    let mut sn = new_size - 1;
+
+    while fn_ & 1 == 1 {
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    let mut fr = first;
+    let mut sr = first;
+
+    for c in path {
+        if sn == 0 {
+            return false;
+        }
+
+        if fn_ & 1 == 1 || fn_ == sn {
+            fr = node_hash(c, &fr);
+            sr = node_hash(c, &sr);
+            shift_to_right_edge(&mut fn_, &mut sn);
+        } else {
+            sr = node_hash(&sr, c);
+        }
+
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    sn == 0 && fr == *old_root && sr == *new_root
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Append-only Merkle tree that keeps only the roots of its maximal complete subtrees, so memory use is O(log n).
+/// Suitable for maintaining a log's root hash when proofs are generated elsewhere
+#[derive(Clone, Debug, Default)]
+pub struct CompactMerkleTree {
+    subtree_roots: Vec<Digest>,
+    size: u64,
+}
+
+impl CompactMerkleTree {
+    pub fn new() -> CompactMerkleTree {
+        CompactMerkleTree::default()
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.push_leaf_hash(leaf_hash(data));
+    }
+
+    pub fn push_leaf_hash(&mut self, leaf_hash: Digest) {
+        let mut root = leaf_hash;
+
+        // Each set low-order bit of the old size is a complete subtree of equal height that can now be merged
+        let mut size = self.size;
+        while size & 1 == 1 {
+            if let Some(left) = self.subtree_roots.pop() {
+                root = node_hash(&left, &root);
+            }
+            size >>= 1;
+        }
+
+        self.subtree_roots.push(root);
+        self.size += 1;
+    }
+
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn root(&self) -> Digest {
+        let mut subtree_roots = self.subtree_roots.iter().rev();
+
+        match subtree_roots.next() {
+            None => Sha256::digest(b""),
+            Some(&last) => subtree_roots.fold(last, |r, left| node_hash(left, &r)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests;
@@ -0,0 +1,116 @@
+use super::*;
+
+// The leaves and expected roots of the RFC 6962 reference test tree
+static LEAVES: [&[u8]; 8] = [
+    b"",
+    b"\x00",
+    b"\x10",
+    b"\x20\x21",
+    b"\x30\x31",
+    b"\x40\x41\x42\x43",
+    b"\x50\x51\x52\x53\x54\x55\x56\x57",
+    b"\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f",
+];
+
+static ROOTS: [&str; 8] = [
+    "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+    "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+    "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+    "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+    "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+    "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+    "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+    "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+];
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_calculate_reference_roots() -> Result<(), String> {
+    let mut compact = CompactMerkleTree::new();
+
+    for n in 1..=LEAVES.len() {
+        let tree = MerkleTree::from_leaves(LEAVES[..n].iter().copied());
+        let root = tree.root().to_string();
+        compact.push(LEAVES[n - 1]);
+        let compact_root = compact.root().to_string();
+
+        if root != ROOTS[n - 1] || compact_root != ROOTS[n - 1] {
+            return Err(format!(
+                "Root of {n} leaves was {root} (compact {compact_root}), expected {}",
+                ROOTS[n - 1]
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_verify_inclusion_proofs() -> Result<(), String> {
+    for n in 1..=LEAVES.len() {
+        let tree = MerkleTree::from_leaves(LEAVES[..n].iter().copied());
+        let root = tree.root();
+
+        for m in 0..n {
+            let proof = tree.inclusion_proof(m).ok_or("Missing inclusion proof")?;
+            let leaf = tree.leaf_hashes()[m];
+
+            if !verify_inclusion(&leaf, m as u64, n as u64, &proof, &root) {
+                return Err(format!("Inclusion proof for leaf {m} of {n} failed"));
+            }
+
+            // Neither a different leaf nor a different position may verify
+            if m > 0 && verify_inclusion(&leaf, m as u64 - 1, n as u64, &proof, &root) {
+                return Err(format!(
+                    "Inclusion proof for leaf {m} of {n} verified at {}",
+                    m - 1
+                ));
+            }
+
+            if verify_inclusion(&leaf_hash(b"x"), m as u64, n as u64, &proof, &root) {
+                return Err(format!(
+                    "Inclusion proof for leaf {m} of {n} verified a bogus leaf"
+                ));
+            }
+        }
+
+        if tree.inclusion_proof(n).is_some() {
+            return Err(format!(
+                "Inclusion proof generated for non-existent leaf {n}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_verify_consistency_proofs() -> Result<(), String> {
+    let tree = MerkleTree::from_leaves(LEAVES.iter().copied());
+
+    for n in 1..=LEAVES.len() {
+        let new_tree = MerkleTree::from_leaves(LEAVES[..n].iter().copied());
+        let new_root = new_tree.root();
+
+        for m in 1..=n {
+            let old_root = root_hash(&tree.leaf_hashes()[..m]);
+            let proof = new_tree
+                .consistency_proof(m)
+                .ok_or("Missing consistency proof")?;
+
+            if !verify_consistency(m as u64, n as u64, &old_root, &new_root, &proof) {
+                return Err(format!("Consistency proof from {m} to {n} leaves failed"));
+            }
+
+            if m < n && verify_consistency(m as u64, n as u64, &new_root, &old_root, &proof) {
+                return Err(format!(
+                    "Consistency proof from {m} to {n} verified swapped roots"
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::{fs::File, io, os::fd::AsRawFd, ptr, slice};
+
+static ERR_MSG_SIGBUS: &[u8] = b"Error: file truncated while being hashed\n";
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// A read-only, private memory mapping of an entire file.
+/// The mapping is removed when this value is dropped
+pub struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+impl Mmap {
+    /// Map the first `len` bytes of `file` into memory.
+    /// `len` must be greater than zero and must not exceed the size of the file
+    pub fn map(file: &File, len: usize) -> io::Result<Mmap> {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        // The file will be read front to back exactly once, so this is only ever a hint to the kernel
+        let _ = unsafe { libc::madvise(ptr, len, libc::MADV_SEQUENTIAL) };
+
+        Ok(Mmap { ptr, len })
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        let _ = unsafe { libc::munmap(self.ptr, self.len) };
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// If a mapped file is truncated by another process, touching a page beyond the new end of file raises `SIGBUS`.
+/// Rather than letting the process dump core, report the problem and exit with a non-zero return code
+pub fn exit_on_sigbus() {
+    extern "C" fn on_sigbus(_: libc::c_int) {
+        unsafe {
+            libc::write(2, ERR_MSG_SIGBUS.as_ptr() as *const _, ERR_MSG_SIGBUS.len());
+            libc::_exit(1);
+        }
+    }
+
+    unsafe {
+        libc::signal(libc::SIGBUS, on_sigbus as *const () as libc::sighandler_t);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests;
@@ -0,0 +1,25 @@
+use super::*;
+use std::{env, fs, io::Write, process};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_map_file_contents() -> Result<(), String> {
+    let path = env::temp_dir().join(format!("sha256_mmap_{}", process::id()));
+    let content = b"The quick brown fox jumps over the lazy dog";
+
+    let mut file = File::create(&path).map_err(|e| e.to_string())?;
+    file.write_all(content).map_err(|e| e.to_string())?;
+
+    let file = File::open(&path).map_err(|e| e.to_string())?;
+    let map = Mmap::map(&file, content.len()).map_err(|e| e.to_string())?;
+    let mapped = map.as_slice().to_vec();
+
+    drop(map);
+    let _ = fs::remove_file(&path);
+
+    if mapped != content {
+        return Err(format!("Mapped contents {mapped:?}, expected {content:?}"));
+    }
+
+    Ok(())
+}
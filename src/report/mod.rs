@@ -0,0 +1,360 @@
+use crate::{
+    encoding::base64_encode_into,
+    manifest,
+    text::{HEX_CHARS, NumBuf, hex_encode_into},
+};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Per-file output records
+//
+// Records are written into a byte buffer without using `format!` so that the WASI binary can share this code without
+// pulling in Rust's formatting machinery.
+//
+// JSON and CSV are text formats, so a path that is not valid UTF-8 is written with each invalid byte sequence replaced
+// by U+FFFD, and the exact path bytes are also supplied in base64 as `path_base64`.  For valid UTF-8 paths,
+// `path_base64` is null (JSON) or empty (CSV)
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+static CSV_HEADER: &[u8] = b"path,path_base64,size,algorithm,digest,elapsed_us,error\n";
+static NULL: &[u8] = b"null";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// `<digest>  <path>`, one line per file, with the path escaped as `sha256sum` does
+    Text,
+    /// A JSON array of record objects
+    Json,
+    /// One JSON record object per line
+    JsonLines,
+    /// RFC 4180 CSV with a header row
+    Csv,
+}
+
+/// The outcome of hashing one file.
+/// `digest` is the already-encoded digest text, `elapsed_us` the time taken in microseconds
+pub struct Record<'a> {
+    pub path: &'a [u8],
+    pub size: Option<u64>,
+    pub algorithm: &'a str,
+    pub digest: Option<&'a [u8]>,
+    pub elapsed_us: u64,
+    pub error: Option<&'a [u8]>,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "jsonl" => Some(Format::JsonLines),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn write_header(&self, out: &mut Vec<u8>) {
+        match self {
+            Format::Json => out.push(b'['),
+            Format::Csv => out.extend_from_slice(CSV_HEADER),
+            Format::Text | Format::JsonLines => {}
+        }
+    }
+
+    /// Append one record.  `index` is the zero-based position of the record in the output.
+    /// In text format, a failed record produces no output: the error is expected to be reported on stderr instead
+    pub fn write_record(&self, out: &mut Vec<u8>, index: usize, rec: &Record) {
+        match self {
+            Format::Text => {
+                if let Some(digest) = rec.digest {
+                    manifest::write_entry(out, digest, rec.path, false);
+                }
+            }
+            Format::Json | Format::JsonLines => {
+                if *self == Format::Json {
+                    out.extend_from_slice(if index == 0 { b"\n  " } else { b",\n  " });
+                }
+
+                write_json_record(out, rec);
+
+                if *self == Format::JsonLines {
+                    out.push(b'\n');
+                }
+            }
+            Format::Csv => write_csv_record(out, rec),
+        }
+    }
+
+    pub fn write_footer(&self, out: &mut Vec<u8>, record_count: usize) {
+        if *self == Format::Json {
+            out.extend_from_slice(if record_count == 0 { b"]\n" } else { b"\n]\n" });
+        }
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+fn write_json_record(out: &mut Vec<u8>, rec: &Record) {
+    out.extend_from_slice(b"{\"path\":");
+    write_json_string(out, rec.path);
+
+    out.extend_from_slice(b",\"path_base64\":");
+    if str::from_utf8(rec.path).is_ok() {
+        out.extend_from_slice(NULL);
+    } else {
+        out.push(b'"');
+        write_base64(out, rec.path);
+        out.push(b'"');
+    }
+
+    out.extend_from_slice(b",\"size\":");
+    match rec.size {
+        Some(size) => write_decimal(out, size),
+        None => out.extend_from_slice(NULL),
+    }
+
+    out.extend_from_slice(b",\"algorithm\":");
+    write_json_string(out, rec.algorithm.as_bytes());
+
+    out.extend_from_slice(b",\"digest\":");
+    match rec.digest {
+        Some(digest) => write_json_string(out, digest),
+        None => out.extend_from_slice(NULL),
+    }
+
+    out.extend_from_slice(b",\"elapsed_us\":");
+    write_decimal(out, rec.elapsed_us);
+
+    out.extend_from_slice(b",\"error\":");
+    match rec.error {
+        Some(error) => write_json_string(out, error),
+        None => out.extend_from_slice(NULL),
+    }
+
+    out.push(b'}');
+}
+
+/// A quoted JSON string with invalid UTF-8 replaced by U+FFFD
+pub fn write_json_string(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.push(b'"');
+
+    for chunk in bytes.utf8_chunks() {
+        for &b in chunk.valid().as_bytes() {
+            match b {
+                b'"' => out.extend_from_slice(b"\\\""),
+                b'\\' => out.extend_from_slice(b"\\\\"),
+                b'\n' => out.extend_from_slice(b"\\n"),
+                b'\r' => out.extend_from_slice(b"\\r"),
+                b'\t' => out.extend_from_slice(b"\\t"),
+                0x00..=0x1F | 0x7F => {
+                    let mut escape = *b"\\u0000";
+                    hex_encode_into(&[b], HEX_CHARS, &mut escape[4..]);
+                    out.extend_from_slice(&escape);
+                }
+                _ => out.push(b),
+            }
+        }
+
+        if !chunk.invalid().is_empty() {
+            out.extend_from_slice("\u{FFFD}".as_bytes());
+        }
+    }
+
+    out.push(b'"');
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+fn write_csv_record(out: &mut Vec<u8>, rec: &Record) {
+    write_csv_field(out, rec.path);
+    out.push(b',');
+
+    if str::from_utf8(rec.path).is_err() {
+        write_base64(out, rec.path);
+    }
+    out.push(b',');
+
+    if let Some(size) = rec.size {
+        write_decimal(out, size);
+    }
+    out.push(b',');
+
+    write_csv_field(out, rec.algorithm.as_bytes());
+    out.push(b',');
+
+    if let Some(digest) = rec.digest {
+        write_csv_field(out, digest);
+    }
+    out.push(b',');
+
+    write_decimal(out, rec.elapsed_us);
+    out.push(b',');
+
+    if let Some(error) = rec.error {
+        write_csv_field(out, error);
+    }
+    out.push(b'\n');
+}
+
+/// A CSV field, quoted only when it contains a comma, quote or line break, with invalid UTF-8 replaced by U+FFFD
+fn write_csv_field(out: &mut Vec<u8>, bytes: &[u8]) {
+    let needs_quotes = bytes
+        .iter()
+        .any(|b| matches!(b, b',' | b'"' | b'\n' | b'\r'));
+
+    if needs_quotes {
+        out.push(b'"');
+    }
+
+    for chunk in bytes.utf8_chunks() {
+        for &b in chunk.valid().as_bytes() {
+            if b == b'"' {
+                out.push(b'"');
+            }
+            out.push(b);
+        }
+
+        if !chunk.invalid().is_empty() {
+            out.extend_from_slice("\u{FFFD}".as_bytes());
+        }
+    }
+
+    if needs_quotes {
+        out.push(b'"');
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Progress and run statistics
+//
+// Both are written to stderr so that stdout remains a manifest.  Sizes and rates are given in decimal megabytes, with
+// one decimal place calculated in integer arithmetic
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+const PROGRESS_BAR_WIDTH: u64 = 30;
+
+/// Returns the cursor to the start of the line and erases it, removing a progress bar
+pub static CLEAR_LINE: &[u8] = b"\r\x1b[K";
+
+/// Totals for the `--stats` summary
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl Stats {
+    pub fn add(&mut self, size: u64) {
+        self.files += 1;
+        self.bytes += size;
+    }
+
+    /// `<files> files, <bytes> bytes in <seconds> s (<rate> MB/s)`
+    pub fn write(&self, out: &mut Vec<u8>, elapsed_us: u64) {
+        write_decimal(out, self.files);
+        out.extend_from_slice(if self.files == 1 {
+            b" file, "
+        } else {
+            b" files, "
+        });
+        write_decimal(out, self.bytes);
+        out.extend_from_slice(b" bytes in ");
+        write_decimal(out, elapsed_us / 1_000_000);
+        out.push(b'.');
+        write_padded(out, elapsed_us / 1000 % 1000, 3);
+        out.extend_from_slice(b" s (");
+        write_megabytes(out, bytes_per_sec(self.bytes, elapsed_us));
+        out.extend_from_slice(b" MB/s)\n");
+    }
+}
+
+/// Redraw a progress bar over the current line: `[####----]  <done> / <total> MB  <n>%  <rate> MB/s  ETA <m:ss>`.
+/// A `total` of 0 means the size is not known in advance (e.g. a pipe), so only the amount hashed and the rate are shown
+pub fn write_progress(out: &mut Vec<u8>, done: u64, total: u64, elapsed_us: u64) {
+    let rate = bytes_per_sec(done, elapsed_us);
+    out.push(b'\r');
+
+    if total > 0 {
+        let done = done.min(total);
+        let filled = done * PROGRESS_BAR_WIDTH / total;
+
+        out.push(b'[');
+        out.resize(out.len() + filled as usize, b'#');
+        out.resize(out.len() + (PROGRESS_BAR_WIDTH - filled) as usize, b'-');
+        out.extend_from_slice(b"]  ");
+        write_megabytes(out, done);
+        out.extend_from_slice(b" / ");
+        write_megabytes(out, total);
+        out.extend_from_slice(b" MB  ");
+        write_decimal(out, (done as u128 * 100 / total as u128) as u64);
+        out.extend_from_slice(b"%  ");
+    } else {
+        write_megabytes(out, done);
+        out.extend_from_slice(b" MB  ");
+    }
+
+    write_megabytes(out, rate);
+    out.extend_from_slice(b" MB/s");
+
+    if total > 0 {
+        out.extend_from_slice(b"  ETA ");
+
+        if rate > 0 {
+            write_duration(out, (total - done.min(total)).div_ceil(rate));
+        } else {
+            out.extend_from_slice(b"-:--");
+        }
+    }
+
+    // Erase whatever is left of a longer previous line
+    out.extend_from_slice(b"\x1b[K");
+}
+
+fn bytes_per_sec(bytes: u64, elapsed_us: u64) -> u64 {
+    if elapsed_us == 0 {
+        0
+    } else {
+        (bytes as u128 * 1_000_000 / elapsed_us as u128) as u64
+    }
+}
+
+/// Bytes as megabytes to one decimal place, rounded down
+fn write_megabytes(out: &mut Vec<u8>, bytes: u64) {
+    write_decimal(out, bytes / 1_000_000);
+    out.push(b'.');
+    write_decimal(out, bytes / 100_000 % 10);
+}
+
+/// `m:ss`, or `h:mm:ss` from one hour
+fn write_duration(out: &mut Vec<u8>, secs: u64) {
+    if secs >= 3600 {
+        write_decimal(out, secs / 3600);
+        out.push(b':');
+        write_padded(out, secs / 60 % 60, 2);
+    } else {
+        write_decimal(out, secs / 60);
+    }
+
+    out.push(b':');
+    write_padded(out, secs % 60, 2);
+}
+
+/// A number below `10^width`, with leading zeros
+fn write_padded(out: &mut Vec<u8>, n: u64, width: usize) {
+    let start = out.len();
+    write_decimal(out, n);
+    let len = out.len() - start;
+
+    if len < width {
+        out.splice(start..start, core::iter::repeat_n(b'0', width - len));
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+fn write_base64(out: &mut Vec<u8>, bytes: &[u8]) {
+    let start = out.len();
+    out.resize(start + bytes.len().div_ceil(3) * 4, 0);
+    base64_encode_into(bytes, &mut out[start..]);
+}
+
+pub fn write_decimal(out: &mut Vec<u8>, n: u64) {
+    out.extend_from_slice(NumBuf::decimal(n).as_bytes());
+}
+
+#[cfg(test)]
+mod unit_tests;
@@ -0,0 +1,152 @@
+use super::*;
+
+fn record<'a>(path: &'a [u8], digest: Option<&'a [u8]>, error: Option<&'a [u8]>) -> Record<'a> {
+    Record {
+        path,
+        size: digest.map(|_| 3),
+        algorithm: "sha256",
+        digest,
+        elapsed_us: 42,
+        error,
+    }
+}
+
+static DIGEST: &[u8] = b"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_escape_json_paths() -> Result<(), String> {
+    let vectors: [(&[u8], &str); 4] = [
+        (b"abc", r#""abc""#),
+        (b"a\"b\\c\nd", r#""a\"b\\c\nd""#),
+        (b"tab\there\x01", r#""tab\there\u0001""#),
+        (b"bad\xFFname", "\"bad\u{FFFD}name\""),
+    ];
+
+    for (path, ans) in vectors {
+        let mut out = Vec::new();
+        write_json_string(&mut out, path);
+
+        if out != ans.as_bytes() {
+            return Err(format!(
+                "write_json_string({path:?}) returned {}, expected {ans}",
+                String::from_utf8_lossy(&out)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_write_json_array() -> Result<(), String> {
+    let mut out = Vec::new();
+    Format::Json.write_header(&mut out);
+    Format::Json.write_record(&mut out, 0, &record(b"abc.txt", Some(DIGEST), None));
+    Format::Json.write_record(&mut out, 1, &record(b"\xFF", None, Some(b"No such file")));
+    Format::Json.write_footer(&mut out, 2);
+
+    let ans = "[\n  {\"path\":\"abc.txt\",\"path_base64\":null,\"size\":3,\"algorithm\":\"sha256\",\"digest\":\"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\",\"elapsed_us\":42,\"error\":null},\n  {\"path\":\"\u{FFFD}\",\"path_base64\":\"/w==\",\"size\":null,\"algorithm\":\"sha256\",\"digest\":null,\"elapsed_us\":42,\"error\":\"No such file\"}\n]\n";
+
+    if out != ans.as_bytes() {
+        return Err(format!("JSON output was {}", String::from_utf8_lossy(&out)));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_quote_csv_fields() -> Result<(), String> {
+    let mut out = Vec::new();
+    Format::Csv.write_header(&mut out);
+    Format::Csv.write_record(&mut out, 0, &record(b"a,\"b\"\n", Some(DIGEST), None));
+    Format::Csv.write_record(&mut out, 1, &record(b"\xFF", None, Some(b"No such file")));
+
+    let ans = "path,path_base64,size,algorithm,digest,elapsed_us,error\n\
+        \"a,\"\"b\"\"\n\",,3,sha256,ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad,42,\n\
+        \u{FFFD},/w==,,sha256,,42,No such file\n";
+
+    if out != ans.as_bytes() {
+        return Err(format!("CSV output was {}", String::from_utf8_lossy(&out)));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_draw_progress_bar() -> Result<(), String> {
+    let vectors: [(u64, u64, u64, &str); 4] = [
+        (
+            500_000_000,
+            2_000_000_000,
+            2_000_000,
+            "\r[#######-----------------------]  500.0 / 2000.0 MB  25%  250.0 MB/s  ETA 0:06\x1b[K",
+        ),
+        (
+            0,
+            2_000_000_000,
+            0,
+            "\r[------------------------------]  0.0 / 2000.0 MB  0%  0.0 MB/s  ETA -:--\x1b[K",
+        ),
+        (
+            1_000,
+            4_000_000_000,
+            1_000_000,
+            "\r[------------------------------]  0.0 / 4000.0 MB  0%  0.0 MB/s  ETA 1111:06:39\x1b[K",
+        ),
+        (12_345_678, 0, 1_000_000, "\r12.3 MB  12.3 MB/s\x1b[K"),
+    ];
+
+    for (done, total, elapsed_us, ans) in vectors {
+        let mut out = Vec::new();
+        write_progress(&mut out, done, total, elapsed_us);
+
+        if out != ans.as_bytes() {
+            return Err(format!(
+                "{done}/{total}: got {:?}",
+                String::from_utf8_lossy(&out)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_write_stats() -> Result<(), String> {
+    let mut stats = Stats::default();
+    stats.add(3_000_000);
+    stats.add(1_500_000);
+
+    let vectors: [(Stats, u64, &str); 3] = [
+        (
+            stats,
+            1_500_000,
+            "2 files, 4500000 bytes in 1.500 s (3.0 MB/s)\n",
+        ),
+        (
+            Stats { files: 1, bytes: 3 },
+            42,
+            "1 file, 3 bytes in 0.000 s (0.0 MB/s)\n",
+        ),
+        (
+            Stats::default(),
+            0,
+            "0 files, 0 bytes in 0.000 s (0.0 MB/s)\n",
+        ),
+    ];
+
+    for (stats, elapsed_us, ans) in vectors {
+        let mut out = Vec::new();
+        stats.write(&mut out, elapsed_us);
+
+        if out != ans.as_bytes() {
+            return Err(format!("got {:?}", String::from_utf8_lossy(&out)));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use crate::text::{HEX_CHARS, hex_encode_into};
+use core::fmt;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// A 32-byte SHA256 message digest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Digest(pub [u8; 32]);
+
+impl Digest {
+    /// Serialise the final hash values as big-endian bytes
+    pub fn from_hash_vals(hash_vals: &[u32; 8]) -> Digest {
+        let mut bytes = [0u8; 32];
+
+        for (i, &val) in hash_vals.iter().enumerate() {
+            bytes[i * 4..i * 4 + 4].copy_from_slice(&val.to_be_bytes());
+        }
+
+        Digest(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Compare two digests in time that does not depend on where they first differ, so that checking a secret or
+    /// attacker-supplied digest reveals nothing through timing
+    pub fn ct_eq(&self, other: &Digest) -> bool {
+        let diff = self
+            .0
+            .iter()
+            .zip(&other.0)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+
+        // Stop the compiler from turning the fold back into an early-exit comparison
+        core::hint::black_box(diff) == 0
+    }
+
+    /// The digest with its byte order reversed, as Bitcoin displays block and transaction hashes
+    pub fn reversed(&self) -> Digest {
+        let mut bytes = self.0;
+        bytes.reverse();
+        Digest(bytes)
+    }
+}
+
+/// Lowercase hex
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut hex = [0u8; 64];
+        hex_encode_into(&self.0, HEX_CHARS, &mut hex);
+
+        // Only ever contains ASCII hex digits
+        f.write_str(str::from_utf8(&hex).unwrap_or_default())
+    }
+}
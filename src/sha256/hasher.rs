@@ -0,0 +1,98 @@
+use super::{Digest, H0, phase_1, phase_2, phase_final};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Incremental SHA256 hasher.
+/// Input can be supplied in pieces of any size: whole message blocks are passed straight to the compression functions
+/// and only a trailing partial block is buffered until more data arrives
+#[derive(Clone)]
+pub struct Sha256 {
+    hash_vals: [u32; 8],
+    msg_schedule: [u32; 64],
+    msg_blk: [u8; 64],
+    msg_blk_len: usize,
+    msg_len: u64,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha256 {
+    pub fn new() -> Sha256 {
+        Sha256::from_state(H0, 0)
+    }
+
+    /// Resume hashing from an intermediate state (a "midstate").
+    /// `msg_len` is the number of bytes already compressed into `hash_vals` and must be a multiple of 64
+    pub fn from_state(hash_vals: [u32; 8], msg_len: u64) -> Sha256 {
+        debug_assert!(
+            msg_len.is_multiple_of(64),
+            "midstate must lie on a message block boundary"
+        );
+
+        Sha256 {
+            hash_vals,
+            msg_schedule: [0u32; 64],
+            msg_blk: [0u8; 64],
+            msg_blk_len: 0,
+            msg_len,
+        }
+    }
+
+    /// The current hash values and the number of bytes compressed into them.
+    /// Only meaningful as a midstate when no partial message block is pending
+    pub fn state(&self) -> ([u32; 8], u64) {
+        (self.hash_vals, self.msg_len - self.msg_blk_len as u64)
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.msg_len += data.len() as u64;
+
+        // Top up a previously buffered partial message block
+        if self.msg_blk_len > 0 {
+            let n = (64 - self.msg_blk_len).min(data.len());
+            self.msg_blk[self.msg_blk_len..self.msg_blk_len + n].copy_from_slice(&data[..n]);
+            self.msg_blk_len += n;
+            data = &data[n..];
+
+            if self.msg_blk_len < 64 {
+                return;
+            }
+
+            phase_1(&self.msg_blk, &mut self.msg_schedule);
+            phase_2(&self.msg_schedule, &mut self.hash_vals);
+            self.msg_blk_len = 0;
+        }
+
+        let mut msg_blks = data.chunks_exact(64);
+
+        for msg_blk in &mut msg_blks {
+            phase_1(msg_blk, &mut self.msg_schedule);
+            phase_2(&self.msg_schedule, &mut self.hash_vals);
+        }
+
+        let rem = msg_blks.remainder();
+        self.msg_blk[..rem.len()].copy_from_slice(rem);
+        self.msg_blk_len = rem.len();
+    }
+
+    pub fn finalize(mut self) -> Digest {
+        phase_final(
+            &self.msg_blk[..self.msg_blk_len],
+            self.msg_len,
+            &mut self.msg_schedule,
+            &mut self.hash_vals,
+        );
+
+        Digest::from_hash_vals(&self.hash_vals)
+    }
+
+    /// One-shot hash of a complete message
+    pub fn digest(data: &[u8]) -> Digest {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
@@ -1,3 +1,9 @@
+mod digest;
+mod hasher;
+
+pub use digest::*;
+pub use hasher::*;
+
 // The first 32 bits of the fractional part of the cube roots of the first 64 primes 2..311
 static CONSTANTS: [u32; 64] = [
     0x428A2F98, 0x71374491, 0xB5C0FBCF, 0xE9B5DBA5, 0x3956C25B, 0x59F111F1, 0x923F82A4, 0xAB1C5ED5,
@@ -10,6 +16,11 @@ static CONSTANTS: [u32; 64] = [
     0x748F82EE, 0x78A5636F, 0x84C87814, 0x8CC70208, 0x90BEFFFA, 0xA4506CEB, 0xBEF9A3F7, 0xC67178F2,
 ];
 
+/// The initial hash values: the first 32 bits of the fractional part of the square roots of the first 8 primes 2..19
+pub static H0: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
 // Internal SHA256 machinery
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
@@ -94,5 +105,29 @@ pub fn phase_2(msg_schedule: &[u32; 64], hash_vals: &mut [u32; 8]) {
     hash_vals[7] = hash_vals[7].wrapping_add(h);
 }
 
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Append the EOD marker, zero padding and message length (in bits) to the final partial message block, then process
+/// the resulting one or two message blocks.
+/// `tail` holds the last `msg_len % 64` bytes of the message and must therefore be shorter than 64 bytes
+pub fn phase_final(
+    tail: &[u8],
+    msg_len: u64,
+    msg_schedule: &mut [u32; 64],
+    hash_vals: &mut [u32; 8],
+) {
+    let mut blks = [0u8; 128];
+    blks[..tail.len()].copy_from_slice(tail);
+    blks[tail.len()] = 0x80;
+
+    // The 8-byte message length must fit after the EOD marker, otherwise an extra block is needed
+    let end = if tail.len() < 56 { 64 } else { 128 };
+    blks[end - 8..end].copy_from_slice(&(msg_len << 3).to_be_bytes());
+
+    for blk in blks[..end].chunks_exact(64) {
+        phase_1(blk, msg_schedule);
+        phase_2(msg_schedule, hash_vals);
+    }
+}
+
 #[cfg(test)]
 mod unit_tests;
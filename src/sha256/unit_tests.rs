@@ -11,7 +11,8 @@ fn should_choose_correctly() -> Result<(), String> {
     if choose(selector, when_hi, when_lo) != ans {
         return Err(format!(
             "choose() returned 0x{:08X}, expected 0x{:08X}",
-            choose(selector, when_hi, when_lo), ans
+            choose(selector, when_hi, when_lo),
+            ans
         ));
     }
 
@@ -23,7 +24,8 @@ fn should_choose_correctly() -> Result<(), String> {
     if choose(selector, when_hi, when_lo) != ans {
         return Err(format!(
             "choose() returned 0x{:08X}, expected 0x{:08X}",
-            choose(selector, when_hi, when_lo), ans
+            choose(selector, when_hi, when_lo),
+            ans
         ));
     }
 
@@ -41,7 +43,8 @@ fn should_calculate_majority() -> Result<(), String> {
     if majority(a, b, c) != ans {
         return Err(format!(
             "majority() returned 0x{:08X}, expected {:08X}",
-            majority(a, b, c), ans
+            majority(a, b, c),
+            ans
         ));
     }
 
@@ -53,7 +56,8 @@ fn should_calculate_majority() -> Result<(), String> {
     if majority(a, b, c) != ans {
         return Err(format!(
             "majority() returned 0x{:08X}, expected {:08X}",
-            majority(a, b, c), ans
+            majority(a, b, c),
+            ans
         ));
     }
 
@@ -71,3 +75,91 @@ fn should_calculate_majority() -> Result<(), String> {
 
     Ok(())
 }
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_pad_final_msg_blk() -> Result<(), String> {
+    // NIST FIPS 180-2 examples: one message that fits in a single block, and one that needs an extra length block
+    let vectors: [(&[u8], [u32; 8]); 2] = [
+        (
+            b"abc",
+            [
+                0xBA7816BF, 0x8F01CFEA, 0x414140DE, 0x5DAE2223, 0xB00361A3, 0x96177A9C, 0xB410FF61,
+                0xF20015AD,
+            ],
+        ),
+        (
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            [
+                0x248D6A61, 0xD20638B8, 0xE5C02693, 0x0C3E6039, 0xA33CE459, 0x64FF2167, 0xF6ECEDD4,
+                0x19DB06C1,
+            ],
+        ),
+    ];
+
+    for (msg, ans) in vectors {
+        let mut msg_schedule = [0u32; 64];
+        let mut hash_vals = H0;
+        phase_final(msg, msg.len() as u64, &mut msg_schedule, &mut hash_vals);
+
+        if hash_vals != ans {
+            return Err(format!(
+                "phase_final() returned {hash_vals:08X?}, expected {ans:08X?}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_incrementally() -> Result<(), String> {
+    let msg: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+    let ans = Sha256::digest(&msg);
+
+    for piece_size in [1, 13, 63, 64, 65, 200] {
+        let mut hasher = Sha256::new();
+
+        for piece in msg.chunks(piece_size) {
+            hasher.update(piece);
+        }
+
+        let digest = hasher.finalize();
+
+        if digest != ans {
+            return Err(format!(
+                "Hashing in {piece_size}-byte pieces returned {digest}, expected {ans}"
+            ));
+        }
+    }
+
+    let empty = Sha256::digest(b"").to_string();
+    if empty != "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855" {
+        return Err(format!("Hash of empty message returned {empty}"));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_compare_digests() -> Result<(), String> {
+    let digest = Sha256::digest(b"abc");
+
+    if !digest.ct_eq(&Sha256::digest(b"abc")) {
+        return Err("Equal digests compared as different".to_string());
+    }
+
+    // A difference in the first, a middle or the last byte
+    for i in [0, 17, 31] {
+        let mut other = digest;
+        other.0[i] ^= 0x80;
+
+        if digest.ct_eq(&other) {
+            return Err(format!("Digests differing in byte {i} compared as equal"));
+        }
+    }
+
+    Ok(())
+}
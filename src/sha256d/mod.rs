@@ -0,0 +1,53 @@
+use crate::sha256::*;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Double SHA256 as used by Bitcoin: SHA256(SHA256(x))
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+
+/// One-shot double SHA256 of a complete message
+pub fn sha256d(data: &[u8]) -> Digest {
+    rehash(&Sha256::digest(data))
+}
+
+/// Hash a 32-byte digest a second time.
+/// The input length is fixed, so the padded message is always exactly one block and the EOD marker and length field
+/// can be written directly without going through the general purpose padding logic
+pub fn rehash(digest: &Digest) -> Digest {
+    let mut msg_blk = [0u8; 64];
+    msg_blk[..32].copy_from_slice(digest.as_bytes());
+    msg_blk[32] = 0x80;
+    msg_blk[62] = 0x01; // Message length of 256 bits
+
+    let mut msg_schedule = [0u32; 64];
+    let mut hash_vals = H0;
+    phase_1(&msg_blk, &mut msg_schedule);
+    phase_2(&msg_schedule, &mut hash_vals);
+
+    Digest::from_hash_vals(&hash_vals)
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Incremental double SHA256 hasher
+#[derive(Clone, Default)]
+pub struct Sha256d {
+    inner: Sha256,
+}
+
+impl Sha256d {
+    pub fn new() -> Sha256d {
+        Sha256d {
+            inner: Sha256::new(),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> Digest {
+        rehash(&self.inner.finalize())
+    }
+}
+
+#[cfg(test)]
+mod unit_tests;
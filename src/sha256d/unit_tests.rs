@@ -0,0 +1,72 @@
+use super::*;
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// Bitcoin block headers (80 bytes) and their block hashes in the usual byte-reversed display order
+static BLOCK_HEADERS: [(&str, &str); 2] = [
+    // Genesis block
+    (
+        "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c",
+        "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+    ),
+    // Block 1
+    (
+        "010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299",
+        "00000000839a8e6886ab5951d76f411475428afc90947ee320161bbf18eb6048",
+    ),
+];
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_block_headers() -> Result<(), String> {
+    for (header, ans) in BLOCK_HEADERS {
+        let block_hash = sha256d(&from_hex(header)).reversed().to_string();
+
+        if block_hash != ans {
+            return Err(format!("sha256d() returned {block_hash}, expected {ans}"));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_match_one_shot_when_streamed() -> Result<(), String> {
+    for (header, ans) in BLOCK_HEADERS {
+        let header = from_hex(header);
+        let mut hasher = Sha256d::new();
+
+        // Split the header across the 64-byte message block boundary
+        for piece in header.chunks(7) {
+            hasher.update(piece);
+        }
+
+        let block_hash = hasher.finalize().reversed().to_string();
+
+        if block_hash != ans {
+            return Err(format!("Sha256d returned {block_hash}, expected {ans}"));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_rehash_like_general_padding() -> Result<(), String> {
+    let first = Sha256::digest(b"abc");
+    let fast = rehash(&first);
+    let slow = Sha256::digest(first.as_bytes());
+
+    if fast != slow {
+        return Err(format!("rehash() returned {fast}, expected {slow}"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::sha256::*;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// BIP-340 tagged hashes: SHA256(SHA256(tag) || SHA256(tag) || msg)
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+
+/// A tag whose 64-byte prefix has already been compressed.
+/// Since the prefix is exactly one message block, the hash values after processing it (the midstate) depend only on
+/// the tag and can be reused for every message hashed under that tag
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaggedHash {
+    midstate: [u32; 8],
+}
+
+impl TaggedHash {
+    pub fn new(tag: &[u8]) -> TaggedHash {
+        let tag_hash = Sha256::digest(tag);
+
+        let mut msg_blk = [0u8; 64];
+        msg_blk[..32].copy_from_slice(tag_hash.as_bytes());
+        msg_blk[32..].copy_from_slice(tag_hash.as_bytes());
+
+        let mut msg_schedule = [0u32; 64];
+        let mut midstate = H0;
+        phase_1(&msg_blk, &mut msg_schedule);
+        phase_2(&msg_schedule, &mut midstate);
+
+        TaggedHash { midstate }
+    }
+
+    pub fn midstate(&self) -> [u32; 8] {
+        self.midstate
+    }
+
+    /// A hasher positioned immediately after the tag prefix, ready to receive the message
+    pub fn hasher(&self) -> Sha256 {
+        Sha256::from_state(self.midstate, 64)
+    }
+
+    pub fn hash(&self, msg: &[u8]) -> Digest {
+        let mut hasher = self.hasher();
+        hasher.update(msg);
+        hasher.finalize()
+    }
+}
+
+/// One-shot tagged hash.
+/// When hashing several messages under the same tag, create a `TaggedHash` once and reuse it instead
+pub fn tagged_hash(tag: &[u8], msg: &[u8]) -> Digest {
+    TaggedHash::new(tag).hash(msg)
+}
+
+#[cfg(test)]
+mod unit_tests;
@@ -0,0 +1,96 @@
+use super::*;
+use crate::encoding::hex_decode;
+
+fn from_hex(hex: &[u8]) -> Result<Vec<u8>, String> {
+    hex_decode(hex).ok_or_else(|| format!("{} is not hex", String::from_utf8_lossy(hex)))
+}
+
+/// BIP-340 test vectors 0 and 1: the public key P, message m and the R half of the signature, followed by the challenge
+/// e = hash_BIP0340/challenge(R || P || m) under which the published signature verifies, i.e. s⋅G = R + e⋅P
+static CHALLENGE_VECTORS: [[&[u8]; 4]; 2] = [
+    [
+        b"F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+        b"0000000000000000000000000000000000000000000000000000000000000000",
+        b"E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA8215",
+        b"6bb6b93a91f2ecc0cd924f4f9baabb5e6eb21745bb00f2cebdaac908bb5d86ce",
+    ],
+    [
+        b"DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        b"243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        b"6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE3341",
+        b"cfb58e748d9648b71fdc909fb7432fc0c954da5bd75cdc9d4804d32648f9839a",
+    ],
+];
+
+// BIP-340 test vector 1
+static AUX_RAND: &[u8] = b"0000000000000000000000000000000000000000000000000000000000000001";
+static MESSAGE: &[u8] = b"243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89";
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_bip340_challenge() -> Result<(), String> {
+    for (index, [public_key, message, signature_r, ans]) in CHALLENGE_VECTORS.iter().enumerate() {
+        let msg = [
+            from_hex(signature_r)?,
+            from_hex(public_key)?,
+            from_hex(message)?,
+        ]
+        .concat();
+        let challenge = tagged_hash(b"BIP0340/challenge", &msg).to_string();
+
+        if challenge.as_bytes() != *ans {
+            return Err(format!(
+                "Vector {index}: tagged_hash() returned {challenge}, expected {}",
+                String::from_utf8_lossy(ans)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_bip340_aux() -> Result<(), String> {
+    // Signing vector 1 with this aux hash reproduces its published signature
+    let ans = "ee8790e3e65f5408c3f41b56a88c4e096126a71abb3a9ccce6efd19dafc7ec32";
+    let aux = tagged_hash(b"BIP0340/aux", &from_hex(AUX_RAND)?).to_string();
+
+    if aux != ans {
+        return Err(format!("tagged_hash() returned {aux}, expected {ans}"));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_match_uncached_prefix() -> Result<(), String> {
+    let tag = b"TapLeaf";
+    let msg = from_hex(MESSAGE)?;
+    let tag_hash = Sha256::digest(tag);
+
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash.as_bytes());
+    hasher.update(tag_hash.as_bytes());
+    hasher.update(&msg);
+    let ans = hasher.finalize();
+
+    let cached = TaggedHash::new(tag);
+    let digest = cached.hash(&msg);
+
+    if digest != ans {
+        return Err(format!("TaggedHash returned {digest}, expected {ans}"));
+    }
+
+    // Reusing the cached midstate must not disturb it
+    let digest = cached.hash(&msg);
+
+    if digest != ans {
+        return Err(format!(
+            "Second TaggedHash returned {digest}, expected {ans}"
+        ));
+    }
+
+    Ok(())
+}
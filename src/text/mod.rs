@@ -0,0 +1,62 @@
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Allocation-free text formatting
+//
+// Bytes and numbers are written as ASCII into fixed-size buffers without `format!`, so that the WASI binary and the
+// allocator-free WASI module can share this code without pulling in Rust's formatting machinery
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+pub static HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
+
+/// Write each byte as two hex digits taken from `hex_chars`.
+/// `out` must be at least twice as long as `bytes`.  Returns the number of bytes written
+pub fn hex_encode_into(bytes: &[u8], hex_chars: &[u8; 16], out: &mut [u8]) -> usize {
+    for (pair, &b) in out.chunks_exact_mut(2).zip(bytes) {
+        pair[0] = hex_chars[(b >> 4) as usize];
+        pair[1] = hex_chars[(b & 0x0F) as usize];
+    }
+
+    bytes.len() * 2
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// An integer formatted as text in a stack buffer
+pub struct NumBuf {
+    digits: [u8; 20], // u64::MAX has 20 decimal digits
+    start: usize,
+}
+
+impl NumBuf {
+    pub fn decimal(n: u64) -> NumBuf {
+        NumBuf::with_radix(n, 10)
+    }
+
+    /// Lowercase hex without a `0x` prefix
+    pub fn hex(n: u64) -> NumBuf {
+        NumBuf::with_radix(n, 16)
+    }
+
+    fn with_radix(mut n: u64, radix: u64) -> NumBuf {
+        let mut buf = NumBuf {
+            digits: [0u8; 20],
+            start: 20,
+        };
+
+        loop {
+            buf.start -= 1;
+            buf.digits[buf.start] = HEX_CHARS[(n % radix) as usize];
+            n /= radix;
+
+            if n == 0 {
+                break;
+            }
+        }
+
+        buf
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.digits[self.start..]
+    }
+}
+
+#[cfg(test)]
+mod unit_tests;
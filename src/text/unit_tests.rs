@@ -0,0 +1,42 @@
+use super::*;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_format_numbers_without_fmt() -> Result<(), String> {
+    let vectors: [(u64, &str, &str); 5] = [
+        (0, "0", "0"),
+        (9, "9", "9"),
+        (44, "44", "2c"),
+        (4096, "4096", "1000"),
+        (u64::MAX, "18446744073709551615", "ffffffffffffffff"),
+    ];
+
+    for (n, dec, hex) in vectors {
+        let (d, h) = (NumBuf::decimal(n), NumBuf::hex(n));
+
+        if d.as_bytes() != dec.as_bytes() || h.as_bytes() != hex.as_bytes() {
+            return Err(format!(
+                "NumBuf formatted {n} as {:?} and {:?}",
+                String::from_utf8_lossy(d.as_bytes()),
+                String::from_utf8_lossy(h.as_bytes())
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_hex_encode_bytes() -> Result<(), String> {
+    let mut out = [0u8; 8];
+    let len = hex_encode_into(&[0x00, 0x2c, 0xa0, 0xff], HEX_CHARS, &mut out);
+
+    if &out[..len] != b"002ca0ff" {
+        return Err(format!(
+            "hex_encode_into() wrote {:?}",
+            String::from_utf8_lossy(&out[..len])
+        ));
+    }
+
+    Ok(())
+}
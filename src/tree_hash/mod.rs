@@ -0,0 +1,195 @@
+use crate::{merkle, sha256::*};
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+};
+
+pub const CHUNK_SIZE: usize = 1024 * 1024; // 1 MiB
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Tree hash constructions.
+/// In each case the input is split into 1 MiB chunks (the last chunk may be shorter), the chunks are hashed
+/// independently and the chunk hashes are then combined into a single root.  An empty input has no chunks and its
+/// tree hash is the hash of the empty string.
+///
+/// * `V1` hashes each chunk as an RFC 6962 Merkle tree leaf, `SHA256(0x00 || chunk)`, and combines them using the
+///   RFC 6962 Merkle Tree Hash, `SHA256(0x01 || left || right)`, where the left subtree always holds the largest power
+///   of two strictly less than the number of chunks.
+/// * `Glacier` reproduces the Amazon S3 Glacier tree hash: each chunk is hashed as `SHA256(chunk)`, then adjacent pairs
+///   of hashes are combined as `SHA256(left || right)` level by level, and an unpaired hash at the end of a level is
+///   promoted unchanged to the next level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeHash {
+    V1,
+    Glacier,
+}
+
+impl TreeHash {
+    pub fn from_name(name: &str) -> Option<TreeHash> {
+        match name {
+            "v1" => Some(TreeHash::V1),
+            "glacier" => Some(TreeHash::Glacier),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TreeHash::V1 => "v1",
+            TreeHash::Glacier => "glacier",
+        }
+    }
+
+    pub fn hash_chunk(&self, chunk: &[u8]) -> Digest {
+        match self {
+            TreeHash::V1 => merkle::leaf_hash(chunk),
+            TreeHash::Glacier => Sha256::digest(chunk),
+        }
+    }
+
+    pub fn root(&self, chunk_hashes: &[Digest]) -> Digest {
+        match self {
+            TreeHash::V1 => merkle::root_hash(chunk_hashes),
+            TreeHash::Glacier => glacier_root(chunk_hashes),
+        }
+    }
+
+    /// One-shot tree hash of data held in memory
+    pub fn digest(&self, data: &[u8]) -> Digest {
+        let chunk_hashes: Vec<Digest> = data
+            .chunks(CHUNK_SIZE)
+            .map(|c| self.hash_chunk(c))
+            .collect();
+        self.root(&chunk_hashes)
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Combine Glacier chunk hashes pairwise, level by level, until a single hash remains
+pub fn glacier_root(chunk_hashes: &[Digest]) -> Digest {
+    if chunk_hashes.is_empty() {
+        return Sha256::digest(b"");
+    }
+
+    let mut level = chunk_hashes.to_vec();
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Sha256::new();
+                    hasher.update(left.as_bytes());
+                    hasher.update(right.as_bytes());
+                    hasher.finalize()
+                }
+                _ => pair[0],
+            })
+            .collect();
+    }
+
+    level[0]
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Tree hash a stream, reading and hashing it one chunk at a time
+pub fn hash_reader(reader: &mut impl Read, tree_hash: TreeHash) -> io::Result<Digest> {
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut chunk_hashes = Vec::new();
+    let mut len = 0;
+
+    loop {
+        let bytes_read = match reader.read(&mut buffer[len..]) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        len += bytes_read;
+
+        // Only the last chunk may be shorter than CHUNK_SIZE
+        if len == CHUNK_SIZE || (bytes_read == 0 && len > 0) {
+            chunk_hashes.push(tree_hash.hash_chunk(&buffer[..len]));
+            len = 0;
+        }
+
+        if bytes_read == 0 {
+            break; // EOF
+        }
+    }
+
+    Ok(tree_hash.root(&chunk_hashes))
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Tree hash a file, hashing its chunks on up to `threads` threads.
+/// Each thread has its own file handle and repeatedly claims the next unhashed chunk, so the chunks are read in
+/// roughly ascending order no matter how many threads are used.
+/// Pipes and special files report no useful size and cannot seek, so they are streamed by `hash_reader` instead
+pub fn hash_file(path: &Path, tree_hash: TreeHash, threads: usize) -> io::Result<Digest> {
+    let metadata = std::fs::metadata(path)?;
+    if !metadata.is_file() {
+        return hash_reader(&mut File::open(path)?, tree_hash);
+    }
+
+    let file_size = metadata.len();
+    let chunk_count = file_size.div_ceil(CHUNK_SIZE as u64);
+    let next_chunk = AtomicU64::new(0);
+
+    let hash_chunks = || -> io::Result<Vec<(u64, Digest)>> {
+        let mut file = File::open(path)?;
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut chunk_hashes = Vec::new();
+
+        loop {
+            let idx = next_chunk.fetch_add(1, Ordering::Relaxed);
+            if idx >= chunk_count {
+                break;
+            }
+
+            let offset = idx * CHUNK_SIZE as u64;
+            let len = (file_size - offset).min(CHUNK_SIZE as u64) as usize;
+
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut buffer[..len])?;
+            chunk_hashes.push((idx, tree_hash.hash_chunk(&buffer[..len])));
+        }
+
+        Ok(chunk_hashes)
+    };
+
+    let threads = threads.clamp(1, chunk_count.max(1) as usize);
+
+    let results: Vec<io::Result<Vec<(u64, Digest)>>> = if threads == 1 {
+        vec![hash_chunks()]
+    } else {
+        thread::scope(|s| {
+            let workers: Vec<_> = (0..threads).map(|_| s.spawn(hash_chunks)).collect();
+
+            workers
+                .into_iter()
+                .map(|w| {
+                    w.join()
+                        .unwrap_or_else(|_| Err(io::Error::other("hashing thread panicked")))
+                })
+                .collect()
+        })
+    };
+
+    let mut chunk_hashes = vec![Digest([0u8; 32]); chunk_count as usize];
+
+    for result in results {
+        for (idx, digest) in result? {
+            chunk_hashes[idx as usize] = digest;
+        }
+    }
+
+    Ok(tree_hash.root(&chunk_hashes))
+}
+
+#[cfg(test)]
+mod unit_tests;
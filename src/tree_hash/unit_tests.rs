@@ -0,0 +1,119 @@
+use super::*;
+use std::{env, fs, process};
+
+fn test_data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_match_single_hash_for_one_glacier_chunk() -> Result<(), String> {
+    let data = test_data(CHUNK_SIZE);
+    let tree = TreeHash::Glacier.digest(&data);
+    let ans = Sha256::digest(&data);
+
+    if tree != ans {
+        return Err(format!(
+            "Glacier tree hash of one chunk was {tree}, expected {ans}"
+        ));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_promote_unpaired_glacier_hash() -> Result<(), String> {
+    // Three chunks: root = H(H(H(c0) || H(c1)) || H(c2))
+    let data = test_data(2 * CHUNK_SIZE + 100);
+    let chunk_hashes: Vec<Digest> = data.chunks(CHUNK_SIZE).map(Sha256::digest).collect();
+
+    let mut pair = Sha256::new();
+    pair.update(chunk_hashes[0].as_bytes());
+    pair.update(chunk_hashes[1].as_bytes());
+    let mut root = Sha256::new();
+    root.update(pair.finalize().as_bytes());
+    root.update(chunk_hashes[2].as_bytes());
+    let ans = root.finalize();
+
+    let tree = TreeHash::Glacier.digest(&data);
+
+    if tree != ans {
+        return Err(format!(
+            "Glacier tree hash of three chunks was {tree}, expected {ans}"
+        ));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_file_in_parallel() -> Result<(), String> {
+    let path = env::temp_dir().join(format!("sha256_tree_hash_{}", process::id()));
+    let data = test_data(5 * CHUNK_SIZE + 12345);
+    fs::write(&path, &data).map_err(|e| e.to_string())?;
+
+    let mut result = Ok(());
+
+    for tree_hash in [TreeHash::V1, TreeHash::Glacier] {
+        let ans = tree_hash.digest(&data);
+
+        for threads in [1, 3, 16] {
+            match hash_file(&path, tree_hash, threads) {
+                Ok(digest) if digest == ans => {}
+                Ok(digest) => {
+                    result = Err(format!(
+                        "{} tree hash on {threads} threads was {digest}, expected {ans}",
+                        tree_hash.name()
+                    ))
+                }
+                Err(e) => result = Err(e.to_string()),
+            }
+        }
+    }
+
+    let _ = fs::remove_file(&path);
+    result
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Reader that hands out at most `max_read` bytes per call, like a pipe
+struct ShortReader<'a> {
+    data: &'a [u8],
+    max_read: usize,
+}
+
+impl Read for ShortReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.max_read).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn should_stream_chunks_despite_short_reads() -> Result<(), String> {
+    for len in [0, 100, CHUNK_SIZE, 3 * CHUNK_SIZE + 4321] {
+        let data = test_data(len);
+
+        for tree_hash in [TreeHash::V1, TreeHash::Glacier] {
+            let ans = tree_hash.digest(&data);
+            let mut reader = ShortReader {
+                data: &data,
+                max_read: 65537,
+            };
+            let digest = hash_reader(&mut reader, tree_hash).map_err(|e| e.to_string())?;
+
+            if digest != ans {
+                return Err(format!(
+                    "{} tree hash of {len} streamed bytes was {digest}, expected {ans}",
+                    tree_hash.name()
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
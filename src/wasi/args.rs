@@ -0,0 +1,32 @@
+use std::ops::Range;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// The command line arguments, as raw bytes.
+/// WASI does not guarantee they are valid UTF-8, and a filename that is not must still reach `path_open` unchanged
+pub struct Args {
+    buf: Vec<u8>,
+    ranges: Vec<Range<usize>>,
+}
+
+impl Args {
+    /// `ranges` locate each argument, without its NUL terminator, in `buf`
+    pub(super) fn new(buf: Vec<u8>, ranges: Vec<Range<usize>>) -> Args {
+        Args { buf, ranges }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        self.ranges.get(index).map(|r| &self.buf[r.clone()])
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &[u8]> {
+        self.ranges.iter().map(|r| &self.buf[r.clone()])
+    }
+}
@@ -0,0 +1,214 @@
+use core::fmt;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Error codes returned by the `wasi_snapshot_preview1` functions, numbered as in the preview1 witx definition
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Errno {
+    Success = 0,
+    TooBig,
+    Acces,
+    AddrInUse,
+    AddrNotAvail,
+    AfNoSupport,
+    Again,
+    Already,
+    Badf,
+    BadMsg,
+    Busy,
+    Canceled,
+    Child,
+    ConnAborted,
+    ConnRefused,
+    ConnReset,
+    Deadlk,
+    DestAddrReq,
+    Dom,
+    Dquot,
+    Exist,
+    Fault,
+    Fbig,
+    HostUnreach,
+    Idrm,
+    Ilseq,
+    InProgress,
+    Intr,
+    Inval,
+    Io,
+    IsConn,
+    IsDir,
+    Loop,
+    Mfile,
+    Mlink,
+    MsgSize,
+    Multihop,
+    NameTooLong,
+    NetDown,
+    NetReset,
+    NetUnreach,
+    Nfile,
+    NoBufs,
+    NoDev,
+    NoEnt,
+    NoExec,
+    NoLck,
+    NoLink,
+    NoMem,
+    NoMsg,
+    NoProtoOpt,
+    NoSpc,
+    NoSys,
+    NotConn,
+    NotDir,
+    NotEmpty,
+    NotRecoverable,
+    NotSock,
+    NotSup,
+    NoTty,
+    Nxio,
+    Overflow,
+    OwnerDead,
+    Perm,
+    Pipe,
+    Proto,
+    ProtoNoSupport,
+    ProtoType,
+    Range,
+    Rofs,
+    Spipe,
+    Srch,
+    Stale,
+    TimedOut,
+    TxtBsy,
+    Xdev,
+    NotCapable,
+}
+
+use Errno::*;
+
+/// Each error code with the name used by the witx definition (and, upper-cased with an `E` prefix, by wasi-libc) and
+/// the message wasi-libc's `strerror` gives for it, indexed by code
+static ERRNOS: [(Errno, &str, &str); 77] = [
+    (Success, "success", "Success"),
+    (TooBig, "2big", "Argument list too long"),
+    (Acces, "acces", "Permission denied"),
+    (AddrInUse, "addrinuse", "Address in use"),
+    (AddrNotAvail, "addrnotavail", "Address not available"),
+    (
+        AfNoSupport,
+        "afnosupport",
+        "Address family not supported by protocol",
+    ),
+    (Again, "again", "Resource temporarily unavailable"),
+    (Already, "already", "Operation already in progress"),
+    (Badf, "badf", "Bad file descriptor"),
+    (BadMsg, "badmsg", "Bad message"),
+    (Busy, "busy", "Resource busy"),
+    (Canceled, "canceled", "Operation canceled"),
+    (Child, "child", "No child process"),
+    (ConnAborted, "connaborted", "Connection aborted"),
+    (ConnRefused, "connrefused", "Connection refused"),
+    (ConnReset, "connreset", "Connection reset by peer"),
+    (Deadlk, "deadlk", "Resource deadlock would occur"),
+    (DestAddrReq, "destaddrreq", "Destination address required"),
+    (Dom, "dom", "Domain error"),
+    (Dquot, "dquot", "Quota exceeded"),
+    (Exist, "exist", "File exists"),
+    (Fault, "fault", "Bad address"),
+    (Fbig, "fbig", "File too large"),
+    (HostUnreach, "hostunreach", "Host is unreachable"),
+    (Idrm, "idrm", "Identifier removed"),
+    (Ilseq, "ilseq", "Illegal byte sequence"),
+    (InProgress, "inprogress", "Operation in progress"),
+    (Intr, "intr", "Interrupted system call"),
+    (Inval, "inval", "Invalid argument"),
+    (Io, "io", "I/O error"),
+    (IsConn, "isconn", "Socket is connected"),
+    (IsDir, "isdir", "Is a directory"),
+    (Loop, "loop", "Symbolic link loop"),
+    (Mfile, "mfile", "No file descriptors available"),
+    (Mlink, "mlink", "Too many links"),
+    (MsgSize, "msgsize", "Message too large"),
+    (Multihop, "multihop", "Multihop attempted"),
+    (NameTooLong, "nametoolong", "Filename too long"),
+    (NetDown, "netdown", "Network is down"),
+    (NetReset, "netreset", "Connection reset by network"),
+    (NetUnreach, "netunreach", "Network unreachable"),
+    (Nfile, "nfile", "Too many open files in system"),
+    (NoBufs, "nobufs", "No buffer space available"),
+    (NoDev, "nodev", "No such device"),
+    (NoEnt, "noent", "No such file or directory"),
+    (NoExec, "noexec", "Exec format error"),
+    (NoLck, "nolck", "No locks available"),
+    (NoLink, "nolink", "Link has been severed"),
+    (NoMem, "nomem", "Out of memory"),
+    (NoMsg, "nomsg", "No message of desired type"),
+    (NoProtoOpt, "noprotoopt", "Protocol not available"),
+    (NoSpc, "nospc", "No space left on device"),
+    (NoSys, "nosys", "Function not implemented"),
+    (NotConn, "notconn", "Socket not connected"),
+    (NotDir, "notdir", "Not a directory"),
+    (NotEmpty, "notempty", "Directory not empty"),
+    (NotRecoverable, "notrecoverable", "State not recoverable"),
+    (NotSock, "notsock", "Not a socket"),
+    (NotSup, "notsup", "Not supported"),
+    (NoTty, "notty", "Not a tty"),
+    (Nxio, "nxio", "No such device or address"),
+    (Overflow, "overflow", "Value too large for data type"),
+    (OwnerDead, "ownerdead", "Previous owner died"),
+    (Perm, "perm", "Operation not permitted"),
+    (Pipe, "pipe", "Broken pipe"),
+    (Proto, "proto", "Protocol error"),
+    (ProtoNoSupport, "protonosupport", "Protocol not supported"),
+    (ProtoType, "prototype", "Protocol wrong type for socket"),
+    (Range, "range", "Result not representable"),
+    (Rofs, "rofs", "Read-only file system"),
+    (Spipe, "spipe", "Invalid seek"),
+    (Srch, "srch", "No such process"),
+    (Stale, "stale", "Stale file handle"),
+    (TimedOut, "timedout", "Operation timed out"),
+    (TxtBsy, "txtbsy", "Text file busy"),
+    (Xdev, "xdev", "Cross-device link"),
+    (NotCapable, "notcapable", "Capabilities insufficient"),
+];
+
+impl Errno {
+    /// Convert a code returned by the host.
+    /// A conforming host only returns codes defined by preview1; anything else is reported as `Io`
+    pub fn from_raw(code: u16) -> Errno {
+        if code <= NotCapable as u16 {
+            // The codes are numbered from 0 without gaps, so every code up to the last one is a variant.  Not looking
+            // the code up in `ERRNOS` keeps the names and messages out of modules that never print them
+            unsafe { core::mem::transmute::<u16, Errno>(code) }
+        } else {
+            Io
+        }
+    }
+
+    pub fn raw(self) -> u16 {
+        self as u16
+    }
+
+    pub fn name(self) -> &'static str {
+        ERRNOS[self as usize].1
+    }
+
+    /// A human-readable description, e.g. "No such file or directory"
+    pub fn message(self) -> &'static str {
+        ERRNOS[self as usize].2
+    }
+}
+
+impl fmt::Display for Errno {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// Map the return value of a raw import to a `Result`
+pub fn check(code: u16) -> Result<(), Errno> {
+    match code {
+        0 => Ok(()),
+        e => Err(Errno::from_raw(e)),
+    }
+}
@@ -0,0 +1,534 @@
+use super::Errno;
+use std::{cell::RefCell, collections::BTreeMap};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// In-memory WASI host
+//
+// Off WebAssembly there is no host to import `wasi_snapshot_preview1` from, so the library's own tests and anything
+// built with the `fake-host` feature (such as the wasi binary's tests) take the same functions from `imports` below.
+// They are served by a `FakeHost` installed on the current thread, which holds the command line, stdin, a tree of
+// virtual files and directories, and the preopens giving access to it.  The host can also be told to return at most a
+// few bytes (or a random number of them) from each read, to accept only a few bytes from each write, or to fail a given
+// call with a given errno.
+//
+// Each test runs on its own thread, so each one installs its own host
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+const FIRST_PREOPEN_FD: u32 = 3;
+
+thread_local! {
+    static HOST: RefCell<FakeHost> = RefCell::new(FakeHost::default());
+}
+
+enum Node {
+    File(Vec<u8>),
+    Dir,
+}
+
+struct OpenFd {
+    /// Path from the root of the virtual tree, without leading or trailing slashes
+    path: Vec<u8>,
+    pos: usize,
+    /// Set for a preopened directory
+    preopen_name: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+pub struct FakeHost {
+    args: Vec<Vec<u8>>,
+    stdin: Vec<u8>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    nodes: BTreeMap<Vec<u8>, Node>,
+    fds: BTreeMap<u32, OpenFd>,
+    /// If not 0, the most bytes a single read returns
+    max_read: usize,
+    /// If not 0, the most bytes a single write accepts
+    max_write: usize,
+    /// If not 0, the xorshift state from which each read picks a length between 1 and `max_read`
+    read_seed: u64,
+    /// Calls to fail, in order, and the errno each should return
+    failures: Vec<(&'static str, Errno)>,
+    clock: u64,
+}
+
+impl FakeHost {
+    pub fn new() -> FakeHost {
+        FakeHost::default()
+    }
+
+    pub fn arg(mut self, arg: &[u8]) -> FakeHost {
+        self.args.push(arg.to_vec());
+        self
+    }
+
+    pub fn stdin(mut self, data: &[u8]) -> FakeHost {
+        self.stdin = data.to_vec();
+        self
+    }
+
+    /// Add a file, and any directories above it, to the virtual tree.  `path` is relative to the root of the tree
+    pub fn file(mut self, path: &[u8], contents: &[u8]) -> FakeHost {
+        let path = normalise(b"", path).expect("file path must stay within the tree");
+        self.add_parents(&path);
+        self.nodes.insert(path, Node::File(contents.to_vec()));
+        self
+    }
+
+    pub fn dir(mut self, path: &[u8]) -> FakeHost {
+        let path = normalise(b"", path).expect("directory path must stay within the tree");
+        self.add_parents(&path);
+        self.nodes.insert(path, Node::Dir);
+        self
+    }
+
+    /// Preopen the directory at `path` in the tree under the name `name`.
+    /// Preopens are numbered from fd 3 in the order they are added, so add them all before installing the host
+    pub fn preopen(mut self, name: &[u8], path: &[u8]) -> FakeHost {
+        let path = normalise(b"", path).expect("preopen path must stay within the tree");
+        self.add_parents(&path);
+        self.nodes.entry(path.clone()).or_insert(Node::Dir);
+
+        let fd = FIRST_PREOPEN_FD + self.fds.len() as u32;
+        self.fds.insert(
+            fd,
+            OpenFd {
+                path,
+                pos: 0,
+                preopen_name: Some(name.to_vec()),
+            },
+        );
+        self
+    }
+
+    /// Return at most `max_read` bytes from each read of a file or stdin
+    pub fn short_reads(mut self, max_read: usize) -> FakeHost {
+        self.max_read = max_read;
+        self
+    }
+
+    /// Return a pseudo-random number of bytes between 1 and `max_read` from each read, repeatable for a given seed
+    pub fn random_reads(mut self, max_read: usize, seed: u64) -> FakeHost {
+        self.max_read = max_read;
+        self.read_seed = seed | 1;
+        self
+    }
+
+    /// Accept at most `max_write` bytes from each write to stdout or stderr
+    pub fn short_writes(mut self, max_write: usize) -> FakeHost {
+        self.max_write = max_write;
+        self
+    }
+
+    /// Make the next call to the import named `call` (e.g. `"fd_read"`) fail with `errno` and do nothing else
+    pub fn fail(mut self, call: &'static str, errno: Errno) -> FakeHost {
+        self.failures.push((call, errno));
+        self
+    }
+
+    /// Replace the current thread's host
+    pub fn install(self) {
+        HOST.with(|host| *host.borrow_mut() = self);
+    }
+
+    fn add_parents(&mut self, path: &[u8]) {
+        self.nodes.insert(Vec::new(), Node::Dir);
+
+        for (i, _) in path.iter().enumerate().filter(|&(_, &b)| b == b'/') {
+            self.nodes.entry(path[..i].to_vec()).or_insert(Node::Dir);
+        }
+    }
+
+    fn take_failure(&mut self, call: &str) -> Option<u16> {
+        let index = self.failures.iter().position(|&(name, _)| name == call)?;
+        Some(self.failures.remove(index).1.raw())
+    }
+
+    /// The tree path of a path relative to an open directory
+    fn resolve(&self, dir_fd: u32, path: &[u8]) -> Result<Vec<u8>, Errno> {
+        let dir = self.fds.get(&dir_fd).ok_or(Errno::Badf)?;
+
+        match self.nodes.get(&dir.path) {
+            Some(Node::Dir) => normalise(&dir.path, path).ok_or(Errno::NotCapable),
+            _ => Err(Errno::NotDir),
+        }
+    }
+
+    /// The names and types of the entries in a directory, in reverse order so that callers cannot rely on the order
+    fn list(&self, dir_path: &[u8]) -> Vec<(Vec<u8>, u8)> {
+        let mut entries = vec![
+            (b".".to_vec(), FILETYPE_DIRECTORY),
+            (b"..".to_vec(), FILETYPE_DIRECTORY),
+        ];
+
+        for (path, node) in &self.nodes {
+            let Some(name) = child_name(dir_path, path) else {
+                continue;
+            };
+
+            entries.push((name.to_vec(), filetype(node)));
+        }
+
+        entries[2..].reverse();
+        entries
+    }
+}
+
+/// Take everything written to stdout on the current thread so far
+pub fn take_stdout() -> Vec<u8> {
+    HOST.with(|host| std::mem::take(&mut host.borrow_mut().stdout))
+}
+
+/// Take everything written to stderr on the current thread so far
+pub fn take_stderr() -> Vec<u8> {
+    HOST.with(|host| std::mem::take(&mut host.borrow_mut().stderr))
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+const OFLAGS_DIRECTORY: u32 = 1 << 1;
+
+fn filetype(node: &Node) -> u8 {
+    match node {
+        Node::File(_) => FILETYPE_REGULAR_FILE,
+        Node::Dir => FILETYPE_DIRECTORY,
+    }
+}
+
+/// Apply `path` to the tree path `base`, returning `None` if `..` would climb out of `base`, as WASI forbids
+fn normalise(base: &[u8], path: &[u8]) -> Option<Vec<u8>> {
+    let mut components: Vec<&[u8]> = base
+        .split(|&b| b == b'/')
+        .filter(|c| !c.is_empty())
+        .collect();
+    let floor = components.len();
+
+    for component in path.split(|&b| b == b'/') {
+        match component {
+            b"" | b"." => {}
+            b".." if components.len() > floor => {
+                components.pop();
+            }
+            b".." => return None,
+            _ => components.push(component),
+        }
+    }
+
+    Some(components.join(&b'/'))
+}
+
+/// The name of `path` if it is directly inside `dir_path`
+fn child_name<'a>(dir_path: &[u8], path: &'a [u8]) -> Option<&'a [u8]> {
+    let name = if dir_path.is_empty() {
+        path
+    } else {
+        path.strip_prefix(dir_path)?.strip_prefix(b"/")?
+    };
+
+    (!name.is_empty() && !name.contains(&b'/')).then_some(name)
+}
+
+fn with_host<T>(f: impl FnOnce(&mut FakeHost) -> T) -> T {
+    HOST.with(|host| f(&mut host.borrow_mut()))
+}
+
+/// Run an import, failing it instead if it has been scripted to fail
+fn call(name: &str, f: impl FnOnce(&mut FakeHost) -> Result<(), Errno>) -> u16 {
+    with_host(|host| match host.take_failure(name) {
+        Some(errno) => errno,
+        None => f(host).err().map_or(0, Errno::raw),
+    })
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Stand-ins for the `wasi_snapshot_preview1` imports, with the same names and signatures
+#[allow(clippy::missing_safety_doc)] // The same contract as the imports they replace
+pub mod imports {
+    use super::super::interface::{Ciovec, Filestat, Iovec, Prestat};
+    use super::*;
+
+    pub unsafe fn args_sizes_get(argc: *mut usize, argv_buf_size: *mut usize) -> u16 {
+        call("args_sizes_get", |host| {
+            unsafe {
+                *argc = host.args.len();
+                *argv_buf_size = host.args.iter().map(|arg| arg.len() + 1).sum();
+            }
+            Ok(())
+        })
+    }
+
+    pub unsafe fn args_get(argv: *mut *mut u8, argv_buf: *mut u8) -> u16 {
+        call("args_get", |host| {
+            let mut offset = 0;
+
+            for (i, arg) in host.args.iter().enumerate() {
+                unsafe {
+                    let dst = argv_buf.add(offset);
+                    dst.copy_from_nonoverlapping(arg.as_ptr(), arg.len());
+                    *dst.add(arg.len()) = 0;
+                    *argv.add(i) = dst;
+                }
+
+                offset += arg.len() + 1;
+            }
+
+            Ok(())
+        })
+    }
+
+    pub unsafe fn fd_prestat_get(fd: u32, buf: *mut Prestat) -> u16 {
+        call("fd_prestat_get", |host| {
+            let name = host
+                .fds
+                .get(&fd)
+                .and_then(|open| open.preopen_name.as_ref());
+            let name = name.ok_or(Errno::Badf)?;
+
+            unsafe {
+                (*buf).tag = 0;
+                (*buf).pr_name_len = name.len();
+            }
+            Ok(())
+        })
+    }
+
+    pub unsafe fn fd_prestat_dir_name(fd: u32, path: *mut u8, path_len: usize) -> u16 {
+        call("fd_prestat_dir_name", |host| {
+            let name = host
+                .fds
+                .get(&fd)
+                .and_then(|open| open.preopen_name.as_ref());
+            let name = name.ok_or(Errno::Badf)?;
+
+            if path_len < name.len() {
+                return Err(Errno::Inval);
+            }
+
+            unsafe { path.copy_from_nonoverlapping(name.as_ptr(), name.len()) };
+            Ok(())
+        })
+    }
+
+    pub unsafe fn fd_filestat_get(fd: u32, buf: *mut Filestat) -> u16 {
+        call("fd_filestat_get", |host| {
+            let open = host.fds.get(&fd).ok_or(Errno::Badf)?;
+            let node = host.nodes.get(&open.path).ok_or(Errno::NoEnt)?;
+            unsafe { write_filestat(buf, node) };
+            Ok(())
+        })
+    }
+
+    pub unsafe fn fd_close(fd: u32) -> u16 {
+        call("fd_close", |host| {
+            host.fds.remove(&fd).map(|_| ()).ok_or(Errno::Badf)
+        })
+    }
+
+    pub unsafe fn fd_readdir(
+        fd: u32,
+        buf: *mut u8,
+        buf_len: usize,
+        cookie: u64,
+        bufused: *mut usize,
+    ) -> u16 {
+        call("fd_readdir", |host| {
+            let open = host.fds.get(&fd).ok_or(Errno::Badf)?;
+
+            if !matches!(host.nodes.get(&open.path), Some(Node::Dir)) {
+                return Err(Errno::NotDir);
+            }
+
+            // Serialise every entry after `cookie`, then return as much as fits, cutting the last entry short
+            let mut bytes = Vec::new();
+
+            for (index, (name, d_type)) in host
+                .list(&open.path)
+                .into_iter()
+                .enumerate()
+                .skip(cookie as usize)
+            {
+                bytes.extend_from_slice(&(index as u64 + 1).to_le_bytes()); // d_next
+                bytes.extend_from_slice(&0u64.to_le_bytes()); // d_ino
+                bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(&[d_type, 0, 0, 0]);
+                bytes.extend_from_slice(&name);
+            }
+
+            let used = bytes.len().min(buf_len);
+
+            unsafe {
+                buf.copy_from_nonoverlapping(bytes.as_ptr(), used);
+                *bufused = used;
+            }
+            Ok(())
+        })
+    }
+
+    pub unsafe fn path_filestat_get(
+        dir_fd: u32,
+        _flags: u32,
+        path: *const u8,
+        path_len: usize,
+        buf: *mut Filestat,
+    ) -> u16 {
+        call("path_filestat_get", |host| {
+            let path = unsafe { std::slice::from_raw_parts(path, path_len) };
+            let node = host
+                .nodes
+                .get(&host.resolve(dir_fd, path)?)
+                .ok_or(Errno::NoEnt)?;
+            unsafe { write_filestat(buf, node) };
+            Ok(())
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn path_open(
+        dir_fd: u32,
+        _dirflags: u32,
+        path: *const u8,
+        path_len: usize,
+        oflags: u32,
+        _fs_rights_base: u64,
+        _fs_rights_inheriting: u64,
+        _fdflags: u32,
+        fd_out: *mut u32,
+    ) -> u16 {
+        call("path_open", |host| {
+            let path = unsafe { std::slice::from_raw_parts(path, path_len) };
+            let path = host.resolve(dir_fd, path)?;
+
+            match host.nodes.get(&path) {
+                None => return Err(Errno::NoEnt),
+                Some(Node::File(_)) if oflags & OFLAGS_DIRECTORY != 0 => return Err(Errno::NotDir),
+                Some(_) => {}
+            }
+
+            let fd = host
+                .fds
+                .last_key_value()
+                .map_or(FIRST_PREOPEN_FD, |(&fd, _)| fd + 1);
+            host.fds.insert(
+                fd,
+                OpenFd {
+                    path,
+                    pos: 0,
+                    preopen_name: None,
+                },
+            );
+
+            unsafe { *fd_out = fd };
+            Ok(())
+        })
+    }
+
+    pub unsafe fn fd_read(fd: u32, iovs: *const Iovec, iovs_len: usize, nread: *mut usize) -> u16 {
+        call("fd_read", |host| {
+            let max_read = if host.max_read == 0 {
+                usize::MAX
+            } else if host.read_seed == 0 {
+                host.max_read
+            } else {
+                host.read_seed ^= host.read_seed << 13;
+                host.read_seed ^= host.read_seed >> 7;
+                host.read_seed ^= host.read_seed << 17;
+                1 + (host.read_seed % host.max_read as u64) as usize
+            };
+
+            let remaining = if fd == 0 {
+                &host.stdin[..]
+            } else {
+                let open = host.fds.get(&fd).ok_or(Errno::Badf)?;
+
+                match host.nodes.get(&open.path) {
+                    Some(Node::File(contents)) => &contents[open.pos.min(contents.len())..],
+                    Some(Node::Dir) => return Err(Errno::IsDir),
+                    None => return Err(Errno::NoEnt),
+                }
+            };
+
+            let mut total = 0;
+            let iovs = unsafe { std::slice::from_raw_parts(iovs, iovs_len) };
+
+            for iov in iovs {
+                let n = iov
+                    .buf_len
+                    .min(remaining.len() - total)
+                    .min(max_read - total);
+                unsafe {
+                    iov.buf
+                        .copy_from_nonoverlapping(remaining[total..].as_ptr(), n)
+                };
+                total += n;
+            }
+
+            if fd == 0 {
+                host.stdin.drain(..total);
+            } else if let Some(open) = host.fds.get_mut(&fd) {
+                open.pos += total;
+            }
+
+            unsafe { *nread = total };
+            Ok(())
+        })
+    }
+
+    pub unsafe fn fd_write(
+        fd: u32,
+        iovs: *const Ciovec,
+        iovs_len: usize,
+        nwritten: *mut usize,
+    ) -> u16 {
+        call("fd_write", |host| {
+            let max_write = if host.max_write == 0 {
+                usize::MAX
+            } else {
+                host.max_write
+            };
+
+            let out = match fd {
+                1 => &mut host.stdout,
+                2 => &mut host.stderr,
+                _ => return Err(Errno::Badf),
+            };
+
+            let mut total = 0;
+            let iovs = unsafe { std::slice::from_raw_parts(iovs, iovs_len) };
+
+            for iov in iovs {
+                let n = iov.buf_len.min(max_write - total);
+                out.extend_from_slice(unsafe { std::slice::from_raw_parts(iov.buf, n) });
+                total += n;
+            }
+
+            unsafe { *nwritten = total };
+            Ok(())
+        })
+    }
+
+    /// Time advances by one millisecond on each call
+    pub unsafe fn clock_time_get(_id: u32, _precision: u64, time: *mut u64) -> u16 {
+        call("clock_time_get", |host| {
+            host.clock += 1_000_000;
+            unsafe { *time = host.clock };
+            Ok(())
+        })
+    }
+
+    /// A program that exits cannot return to the test that ran it, so this panics with the exit status instead
+    pub unsafe fn proc_exit(rval: u32) -> ! {
+        panic!("proc_exit({rval})")
+    }
+
+    unsafe fn write_filestat(buf: *mut Filestat, node: &Node) {
+        let size = match node {
+            Node::File(contents) => contents.len() as u64,
+            Node::Dir => 0,
+        };
+
+        unsafe {
+            (*buf).filetype = filetype(node);
+            (*buf).size = size;
+        }
+    }
+}
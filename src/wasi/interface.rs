@@ -16,13 +16,68 @@ pub struct Ciovec {
     pub buf_len: usize,
 }
 
+/// Matches WASI's __wasi_filestat_t
+#[repr(C)]
+#[derive(Default)]
+pub struct Filestat {
+    pub dev: u64,
+    pub ino: u64,
+    pub filetype: u8,
+    pub nlink: u64,
+    pub size: u64,
+    pub atim: u64,
+    pub mtim: u64,
+    pub ctim: u64,
+}
+
+/// Matches WASI's __wasi_dirent_t.
+/// In the buffer filled by `fd_readdir`, each of these is followed by the `d_namlen` bytes of the entry's name
+#[cfg(feature = "std")]
+#[repr(C)]
+pub struct Dirent {
+    pub d_next: u64,
+    pub d_ino: u64,
+    pub d_namlen: u32,
+    pub d_type: u8,
+}
+
+/// Matches WASI's __wasi_prestat_t.
+/// `tag` 0 (the only kind of preopen in preview1) is a directory, whose name is `pr_name_len` bytes long
+#[repr(C)]
+#[derive(Default)]
+pub struct Prestat {
+    pub tag: u8,
+    pub pr_name_len: usize,
+}
+
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Without the in-memory host, a native program that calls these imports fails to link
+#[cfg(not(all(not(target_arch = "wasm32"), any(test, feature = "fake-host"))))]
 #[link(wasm_import_module = "wasi_snapshot_preview1")]
 unsafe extern "C" {
     pub fn args_sizes_get(argc: *mut usize, argv_buf_size: *mut usize) -> u16;
     pub fn args_get(argv: *mut *mut u8, argv_buf: *mut u8) -> u16;
 
-    pub fn fd_seek(fd: u32, offset: i64, whence: u8, newoffset: *mut u64) -> u16;
+    pub fn fd_prestat_get(fd: u32, buf: *mut Prestat) -> u16;
+    pub fn fd_prestat_dir_name(fd: u32, path: *mut u8, path_len: usize) -> u16;
+
+    pub fn fd_filestat_get(fd: u32, buf: *mut Filestat) -> u16;
+    pub fn fd_close(fd: u32) -> u16;
+    pub fn fd_readdir(
+        fd: u32,
+        buf: *mut u8,
+        buf_len: usize,
+        cookie: u64,
+        bufused: *mut usize,
+    ) -> u16;
+
+    pub fn path_filestat_get(
+        dir_fd: u32,
+        flags: u32,
+        path: *const u8,
+        path_len: usize,
+        buf: *mut Filestat,
+    ) -> u16;
 
     pub fn path_open(
         dir_fd: u32,
@@ -38,4 +93,12 @@ unsafe extern "C" {
 
     pub fn fd_read(fd: u32, iovs: *const Iovec, iovs_len: usize, nread: *mut usize) -> u16;
     pub fn fd_write(fd: u32, iovs: *const Ciovec, iovs_len: usize, nwritten: *mut usize) -> u16;
+
+    pub fn clock_time_get(id: u32, precision: u64, time: *mut u64) -> u16;
+
+    pub fn proc_exit(rval: u32) -> !;
 }
+
+// Off WebAssembly, the imports can only be served by the in-memory host used for testing
+#[cfg(all(not(target_arch = "wasm32"), any(test, feature = "fake-host")))]
+pub use super::fake::imports::*;
@@ -1,145 +1,390 @@
+// Without the `std` feature, only the wrappers that need no allocator are built
+#[cfg(feature = "std")]
+mod args;
+mod errno;
+#[cfg(all(not(target_arch = "wasm32"), any(test, feature = "fake-host")))]
+pub mod fake;
 mod interface;
+#[cfg(feature = "std")]
+mod preopens;
+#[cfg(feature = "std")]
+mod walk;
 
+#[cfg(feature = "std")]
+pub use args::Args;
+pub use errno::Errno;
+#[cfg(feature = "std")]
+pub use preopens::{Preopen, Preopens};
+#[cfg(feature = "std")]
+pub use walk::{DirEntry, Walk, WalkEntry, WalkError, wasi_read_dir};
+
+use crate::text::NumBuf;
+use core::{ffi::CStr, ops::Range};
+use errno::check;
 use interface::*;
-use std::ffi::CStr;
-
-static ERR_MSG_CMD_ARGS: &[u8] = "Unable to fetch command line arguments: 0x".as_bytes();
-static ERR_MSG_CMD_ARGS_TOO_LONG: &[u8] = "Command line arguments too long (>256 chars)".as_bytes();
-static ERR_MSG_FD_SEEK: &[u8] = "Error reading file size: 0x".as_bytes();
-static ERR_MSG_PATH_OPEN: &[u8] = "Unable to open file: 0x".as_bytes();
-static ERR_MSG_BAD_FD: &[u8] = "Bad file descriptor".as_bytes();
-static ERR_MSG_NOENT: &[u8] = "No such file or directory".as_bytes();
-static ERR_MSG_NOT_DIR_SYMLINK: &[u8] =
-    "Neither a directory nor a symlink to a directory".as_bytes();
-static ERR_MSG_NOT_PERMITTED: &[u8] = "Operation not permitted".as_bytes();
-static ERR_MSG_FD_READ: &[u8] = "Error reading file: 0x".as_bytes();
+
+const RIGHTS_FD_READ: u64 = 1 << 1;
+const RIGHTS_PATH_OPEN: u64 = 1 << 13;
+const RIGHTS_FD_READDIR: u64 = 1 << 14;
+const RIGHTS_PATH_FILESTAT_GET: u64 = 1 << 18;
+const RIGHTS_FD_FILESTAT_GET: u64 = 1 << 21;
+
+const RIGHTS_FILE: u64 = RIGHTS_FD_READ | RIGHTS_FD_FILESTAT_GET;
+const RIGHTS_DIRECTORY: u64 =
+    RIGHTS_PATH_OPEN | RIGHTS_FD_READDIR | RIGHTS_PATH_FILESTAT_GET | RIGHTS_FD_FILESTAT_GET;
+
+const OFLAGS_DIRECTORY: u32 = 1 << 1;
+
+/// The host numbers preopened descriptors consecutively from here
+const FIRST_PREOPEN_FD: u32 = 3;
+
+/// The most buffers a single `fd_write` call gathers
+const MAX_IOVS: usize = 8;
+
+static ERRNO_PREFIX: &[u8] = " (errno ".as_bytes();
+static ERRNO_SUFFIX: &[u8] = ")\n".as_bytes();
 
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
 // Rust <--> WASI interface
+//
+// These wrappers are safe to call: each one passes the host pointers to buffers it has borrowed for the duration of
+// the call, and reports failure as a typed `Errno` rather than the raw code.  They print nothing themselves: the caller
+// knows which path or argument failed, so reports the error with `write_error`
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-pub unsafe fn wasi_args_get<'a>(
-    buf: &'a mut [u8],
-    argv: &mut [&'a str; 4], // max 4 cmd line args
-) -> Result<usize, u16> {
-    let mut argc: usize = 0;
-    let mut argv_buf_size: usize = 0;
-    let ret = unsafe { args_sizes_get(&mut argc as *mut usize, &mut argv_buf_size as *mut usize) };
+/// A WASI file descriptor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fd(pub u32);
+
+impl Fd {
+    pub const STDIN: Fd = Fd(0);
+    pub const STDOUT: Fd = Fd(1);
+    pub const STDERR: Fd = Fd(2);
+}
+
+/// A file descriptor this program opened, closed when dropped
+#[derive(Debug)]
+pub struct OwnedFd(Fd);
 
-    if ret != 0 {
-        let _ = unsafe { wasi_fd_write(2, &[ERR_MSG_CMD_ARGS, &ret.to_le_bytes()]).unwrap()};
-        return Err(ret);
+impl OwnedFd {
+    pub fn fd(&self) -> Fd {
+        self.0
     }
+}
 
-    // Avoid buffer overflow
-    if argv_buf_size > buf.len() {
-        let _ = unsafe { wasi_fd_write(2, &[ERR_MSG_CMD_ARGS_TOO_LONG]).unwrap() };
-        return Err(1);
+impl Drop for OwnedFd {
+    fn drop(&mut self) {
+        // There is nothing useful to do if closing fails
+        let _ = wasi_fd_close(self.0);
     }
+}
 
-    let mut raw_ptrs: [*mut u8; 4] = [core::ptr::null_mut(); 4];
+/// Matches WASI's __wasi_filetype_t
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filetype {
+    Unknown = 0,
+    BlockDevice,
+    CharacterDevice,
+    Directory,
+    RegularFile,
+    SocketDgram,
+    SocketStream,
+    SymbolicLink,
+}
 
-    let ret = unsafe { args_get(raw_ptrs.as_mut_ptr(), buf.as_mut_ptr()) };
-    if ret != 0 {
-        let _ = unsafe { wasi_fd_write(2, &[ERR_MSG_CMD_ARGS, &ret.to_le_bytes()]).unwrap()};
-        return Err(1);
+impl Filetype {
+    fn from_raw(filetype: u8) -> Filetype {
+        match filetype {
+            1 => Filetype::BlockDevice,
+            2 => Filetype::CharacterDevice,
+            3 => Filetype::Directory,
+            4 => Filetype::RegularFile,
+            5 => Filetype::SocketDgram,
+            6 => Filetype::SocketStream,
+            7 => Filetype::SymbolicLink,
+            _ => Filetype::Unknown,
+        }
     }
+}
+
+/// The parts of WASI's __wasi_filestat_t this program uses
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileStat {
+    pub filetype: Filetype,
+    pub size: u64,
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Fetch every command line argument, in buffers sized by `args_sizes_get`
+#[cfg(feature = "std")]
+pub fn wasi_args_get() -> Result<Args, Errno> {
+    let (argc, argv_buf_size) = wasi_args_sizes_get()?;
+
+    let mut buf = vec![0u8; argv_buf_size];
+    let mut argv: Vec<*mut u8> = vec![core::ptr::null_mut(); argc];
+    wasi_args_get_into(&mut argv, &mut buf)?;
+
+    let ranges = argv
+        .iter()
+        .map(|&ptr| arg_range(&buf, ptr).ok_or(Errno::Fault))
+        .collect::<Result<_, _>>()?;
 
-    for i in 0..argc.min(argv.len()) {
-        let arg_bytes = unsafe { CStr::from_ptr(raw_ptrs[i] as *const _).to_bytes() };
-        argv[i] = str::from_utf8(arg_bytes).unwrap_or_default();
+    Ok(Args::new(buf, ranges))
+}
+
+/// The number of command line arguments, and the size of the buffer that holds them all with their NUL terminators
+pub fn wasi_args_sizes_get() -> Result<(usize, usize), Errno> {
+    let mut argc: usize = 0;
+    let mut argv_buf_size: usize = 0;
+
+    let ret = unsafe { args_sizes_get(&mut argc, &mut argv_buf_size) };
+
+    check(ret).map(|_| (argc, argv_buf_size))
+}
+
+/// Fetch the command line arguments without allocating: `buf` receives each argument followed by a NUL, and `argv` a
+/// pointer to the start of each one.  Returns the number of arguments, or `TooBig` if `argv` or `buf` is smaller than
+/// `wasi_args_sizes_get` requires
+pub fn wasi_args_get_into(argv: &mut [*mut u8], buf: &mut [u8]) -> Result<usize, Errno> {
+    let (argc, argv_buf_size) = wasi_args_sizes_get()?;
+
+    if argc > argv.len() || argv_buf_size > buf.len() {
+        return Err(Errno::TooBig);
     }
 
-    Ok(argc)
+    let ret = unsafe { args_get(argv.as_mut_ptr(), buf.as_mut_ptr()) };
+
+    check(ret).map(|_| argc)
+}
+
+/// Locate an argument fetched by `wasi_args_get_into` in `buf`, without its NUL terminator.
+/// Only the pointer's offset into `buf` is used, so a pointer the host has got wrong gives `None` rather than a read
+/// outside `buf`
+pub fn arg_range(buf: &[u8], ptr: *mut u8) -> Option<Range<usize>> {
+    let start = (ptr as usize)
+        .checked_sub(buf.as_ptr() as usize)
+        .filter(|&start| start < buf.len())?;
+    let len = CStr::from_bytes_until_nul(&buf[start..])
+        .ok()?
+        .count_bytes();
+
+    Some(start..start + len)
 }
 
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-pub unsafe fn fetch_file_size(fd: u32) -> Result<u64, u16> {
-    let mut new_offset: u64 = 0;
-
-    // Seek to the end of the file
-    let file_size_bytes: u64 = match unsafe { fd_seek(fd, 0, 2, &mut new_offset) } {
-        0 => new_offset,
-        e => {
-            let _ = unsafe { wasi_fd_write(2, &[ERR_MSG_FD_SEEK, &e.to_le_bytes()]).unwrap()};
-            return Err(1);
-        }
-    };
+/// The length of the preopened directory name for `fd`, or `None` if `fd` is preopened but is not a directory
+pub fn wasi_fd_prestat_get(fd: Fd) -> Result<Option<usize>, Errno> {
+    let mut prestat = Prestat::default();
 
-    // Reset seek pointer
-    match unsafe { fd_seek(fd, 0, 0, &mut new_offset) } {
-        0 => Ok(file_size_bytes),
-        e => {
-            let _ = unsafe { wasi_fd_write(2, &[ERR_MSG_FD_SEEK, &e.to_le_bytes()]).unwrap()};
-            Err(1)
-        }
-    }
+    let ret = unsafe { fd_prestat_get(fd.0, &mut prestat) };
+    check(ret)?;
+
+    Ok((prestat.tag == 0).then_some(prestat.pr_name_len))
+}
+
+/// Fill `buf`, whose length must be the name length from `wasi_fd_prestat_get`, with the preopened directory name
+pub fn wasi_fd_prestat_dir_name(fd: Fd, buf: &mut [u8]) -> Result<(), Errno> {
+    let ret = unsafe { fd_prestat_dir_name(fd.0, buf.as_mut_ptr(), buf.len()) };
+    check(ret)
+}
+
+/// Each preopened directory, with the length of its name.
+/// The list ends at the first descriptor that is not a preopen, which the host reports as `Badf`
+pub fn wasi_preopened_dirs() -> impl Iterator<Item = Result<(Fd, usize), Errno>> {
+    (FIRST_PREOPEN_FD..)
+        .map(|fd| (Fd(fd), wasi_fd_prestat_get(Fd(fd))))
+        .take_while(|(_, prestat)| *prestat != Err(Errno::Badf))
+        .filter_map(|(fd, prestat)| match prestat {
+            Ok(Some(name_len)) => Some(Ok((fd, name_len))),
+            Ok(None) => None, // Not a directory
+            Err(errno) => Some(Err(errno)),
+        })
 }
 
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-pub unsafe fn wasi_path_open(dir_fd: u32, path: &str) -> Result<u32, u16> {
-    let mut new_fd: u32 = 0;
+pub fn wasi_fd_filestat_get(fd: Fd) -> Result<FileStat, Errno> {
+    let mut filestat = Filestat::default();
 
-    let ret = unsafe { path_open(
+    let ret = unsafe { fd_filestat_get(fd.0, &mut filestat) };
+    check(ret)?;
+
+    Ok(FileStat {
+        filetype: Filetype::from_raw(filestat.filetype),
+        size: filestat.size,
+    })
+}
+
+pub fn wasi_fd_close(fd: Fd) -> Result<(), Errno> {
+    let ret = unsafe { fd_close(fd.0) };
+    check(ret)
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Open a file for reading
+pub fn wasi_path_open(dir_fd: Fd, path: &[u8]) -> Result<OwnedFd, Errno> {
+    open_at(dir_fd, path, 0, RIGHTS_FILE, 0)
+}
+
+/// Open a directory so that it can be listed, and the files and directories within it opened in turn.
+/// Fails with `NotDir` if the path is not a directory
+pub fn wasi_path_open_dir(dir_fd: Fd, path: &[u8]) -> Result<OwnedFd, Errno> {
+    open_at(
         dir_fd,
-        0, // Don't follow symlinks
-        path.as_ptr(),
-        path.len(),
-        0, // 0 = read only
-        6, // 6 = fd_seek (4) + fd_read (2)
-        0, // inheriting rights
-        0, // fdflags
-        &mut new_fd,
-    )};
-
-    if ret == 0 {
-        Ok(new_fd)
-    } else {
-        let _ = match ret {
-            0x08 => unsafe { wasi_fd_write(2, &[ERR_MSG_BAD_FD]).unwrap() },
-            0x2C => unsafe { wasi_fd_write(2, &[ERR_MSG_NOENT]).unwrap() },
-            0x36 => unsafe { wasi_fd_write(2, &[ERR_MSG_NOT_DIR_SYMLINK]).unwrap() },
-            0x3F => unsafe { wasi_fd_write(2, &[ERR_MSG_NOT_PERMITTED]).unwrap() },
-            _ => unsafe { wasi_fd_write(2, &[ERR_MSG_PATH_OPEN]).unwrap() },
-        };
-
-        Err(ret)
-    }
+        path,
+        OFLAGS_DIRECTORY,
+        RIGHTS_DIRECTORY,
+        RIGHTS_DIRECTORY | RIGHTS_FILE,
+    )
+}
+
+fn open_at(
+    dir_fd: Fd,
+    path: &[u8],
+    oflags: u32,
+    rights: u64,
+    inheriting: u64,
+) -> Result<OwnedFd, Errno> {
+    let mut new_fd: u32 = 0;
+
+    let ret = unsafe {
+        path_open(
+            dir_fd.0,
+            0, // Don't follow symlinks
+            path.as_ptr(),
+            path.len(),
+            oflags,
+            rights,
+            inheriting,
+            0, // fdflags
+            &mut new_fd,
+        )
+    };
+
+    check(ret).map(|_| OwnedFd(Fd(new_fd)))
+}
+
+/// The type and size of a path relative to `dir_fd`.  A symlink is described rather than followed
+pub fn wasi_path_filestat_get(dir_fd: Fd, path: &[u8]) -> Result<FileStat, Errno> {
+    let mut filestat = Filestat::default();
+
+    let ret = unsafe { path_filestat_get(dir_fd.0, 0, path.as_ptr(), path.len(), &mut filestat) };
+    check(ret)?;
+
+    Ok(FileStat {
+        filetype: Filetype::from_raw(filestat.filetype),
+        size: filestat.size,
+    })
+}
+
+/// Fill `buf` with directory entries, starting with the entry after `cookie` (0 for the first entry).
+/// Returns the number of bytes used: if this is less than the length of `buf`, the end of the directory was reached.
+/// Otherwise the last entry may have been cut short
+pub fn wasi_fd_readdir(fd: Fd, buf: &mut [u8], cookie: u64) -> Result<usize, Errno> {
+    let mut bufused: usize = 0;
+
+    let ret = unsafe { fd_readdir(fd.0, buf.as_mut_ptr(), buf.len(), cookie, &mut bufused) };
+
+    check(ret).map(|_| bufused)
 }
 
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-pub unsafe fn wasi_fd_read(fd: u32, buf: &mut [u8]) -> Result<usize, u16> {
+pub fn wasi_fd_read(fd: Fd, buf: &mut [u8]) -> Result<usize, Errno> {
     let iov = Iovec {
         buf: buf.as_mut_ptr(),
         buf_len: buf.len(),
     };
     let mut nread: usize = 0;
 
-    let ret = unsafe { fd_read(fd, &iov, 1, &mut nread) };
+    let ret = unsafe { fd_read(fd.0, &iov, 1, &mut nread) };
 
-    if ret == 0 {
-        Ok(nread)
-    } else {
-        let _ = unsafe { wasi_fd_write(2, &[ERR_MSG_FD_READ, &ret.to_le_bytes()]).unwrap() };
-        Err(ret)
-    }
+    check(ret).map(|_| nread)
 }
 
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-pub unsafe fn wasi_fd_write(fd: u32, bufs: &[&[u8]]) -> Result<usize, u16> {
-    let iovs: Vec<Ciovec> = bufs
-        .iter()
-        .map(|b| Ciovec {
-            buf: b.as_ptr(),
-            buf_len: b.len(),
-        })
-        .collect();
-    let mut bytes_written: usize = 0;
+/// Write the buffers in order, gathering up to `MAX_IOVS` of them into each call so that no allocation is needed.
+/// A write may stop short (e.g. on a full pipe) or be interrupted, so each call resumes wherever the last one stopped
+pub fn wasi_fd_write(fd: Fd, bufs: &[&[u8]]) -> Result<usize, Errno> {
+    let mut iovs = [const {
+        Ciovec {
+            buf: core::ptr::null(),
+            buf_len: 0,
+        }
+    }; MAX_IOVS];
+    let mut total_written: usize = 0;
+
+    for chunk in bufs.chunks(MAX_IOVS) {
+        for (iov, b) in iovs.iter_mut().zip(chunk) {
+            iov.buf = b.as_ptr();
+            iov.buf_len = b.len();
+        }
+
+        let iovs = &mut iovs[..chunk.len()];
+        let mut start = 0;
+        let mut bytes_written: usize = 0;
 
-    let ret = unsafe { fd_write(fd, iovs.as_ptr(), iovs.len(), &mut bytes_written) };
+        loop {
+            // Skip the buffers written in full, including any empty ones, then the written part of the next one
+            while start < iovs.len() && iovs[start].buf_len <= bytes_written {
+                bytes_written -= iovs[start].buf_len;
+                start += 1;
+            }
 
-    if ret == 0 {
-        Ok(bytes_written)
-    } else {
-        Err(ret)
+            let Some(iov) = iovs.get_mut(start) else {
+                break;
+            };
+
+            iov.buf = iov.buf.wrapping_add(bytes_written);
+            iov.buf_len -= bytes_written;
+
+            let pending = &iovs[start..];
+            bytes_written = 0;
+            let ret =
+                unsafe { fd_write(fd.0, pending.as_ptr(), pending.len(), &mut bytes_written) };
+
+            match check(ret) {
+                Ok(()) if bytes_written == 0 => return Err(Errno::Io), // No progress
+                Ok(()) => total_written += bytes_written,
+                Err(Errno::Intr) => bytes_written = 0,
+                Err(errno) => return Err(errno),
+            }
+        }
     }
+
+    Ok(total_written)
 }
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Current time of the given clock in nanoseconds
+pub fn wasi_clock_time_get(clock_id: u32) -> Result<u64, Errno> {
+    let mut time: u64 = 0;
+
+    let ret = unsafe { clock_time_get(clock_id, 1000, &mut time) };
+
+    check(ret).map(|_| time)
+}
+
+/// End the program with the given exit status
+pub fn wasi_proc_exit(status: u32) -> ! {
+    unsafe { proc_exit(status) }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Write `<context...><message> (errno <code>)` to stderr.
+/// For example, `write_error(&[b"sha256: ", path, b": "], errno)`
+pub fn write_error(context: &[&[u8]], errno: Errno) {
+    let code = NumBuf::decimal(errno.raw() as u64);
+
+    let _ = wasi_fd_write(Fd::STDERR, context);
+    let _ = wasi_fd_write(
+        Fd::STDERR,
+        &[
+            errno.message().as_bytes(),
+            ERRNO_PREFIX,
+            code.as_bytes(),
+            ERRNO_SUFFIX,
+        ],
+    );
+}
+
+#[cfg(test)]
+mod unit_tests;
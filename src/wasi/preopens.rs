@@ -0,0 +1,124 @@
+use super::{Errno, Fd, wasi_fd_prestat_dir_name, wasi_preopened_dirs};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Preopened directories
+//
+// A WASI program can only open paths relative to the directories the host has preopened for it.  The host numbers
+// these descriptors consecutively from 3, and names each with the path the program should see (e.g. `.`, `/` or
+// `/data`).  A path argument is resolved, as wasi-libc does, to the preopen whose name is the longest whole-component
+// prefix of the path, and the remainder of the path is then opened relative to that descriptor
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+pub struct Preopen {
+    pub fd: Fd,
+    /// The name given by the host, with any leading `./` removed, so that `.` becomes the empty string
+    pub name: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct Preopens {
+    dirs: Vec<Preopen>,
+}
+
+impl Preopens {
+    /// Ask the host for every preopened directory
+    pub fn discover() -> Result<Preopens, Errno> {
+        let mut preopens = Preopens::default();
+
+        for preopen in wasi_preopened_dirs() {
+            let (fd, name_len) = preopen?;
+            let mut name = vec![0u8; name_len];
+            wasi_fd_prestat_dir_name(fd, &mut name)?;
+
+            // Some hosts include the C string terminator in the name
+            while name.last() == Some(&0) {
+                name.pop();
+            }
+
+            preopens.push(fd, &name);
+        }
+
+        Ok(preopens)
+    }
+
+    pub fn push(&mut self, fd: Fd, name: &[u8]) {
+        self.dirs.push(Preopen {
+            fd,
+            name: strip_dot_slash(name).to_vec(),
+        });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Preopen> {
+        self.dirs.iter()
+    }
+
+    /// Find the preopen containing `path` and the path relative to it.
+    /// Relative paths are resolved against `.` if it is preopened, and otherwise against `/`, which wasi-libc treats
+    /// as the initial working directory.  Returns `None` if no preopen contains the path
+    pub fn resolve<'a>(&self, path: &'a [u8]) -> Option<(Fd, &'a [u8])> {
+        let path = strip_dot_slash(path);
+        let is_absolute = path.first() == Some(&b'/');
+
+        let best = self
+            .dirs
+            .iter()
+            .filter(|dir| prefix_matches(&dir.name, path))
+            .max_by_key(|dir| dir.name.len());
+
+        let (fd, rel_path) = match best {
+            Some(dir) => (dir.fd, &path[dir.name.len()..]),
+
+            // Relative to a root preopen
+            None if !is_absolute => {
+                let root = self.dirs.iter().find(|dir| dir.name == b"/")?;
+                (root.fd, path)
+            }
+            None => return None,
+        };
+
+        // Strip the separator between the preopen name and the rest of the path
+        match trim_leading_slashes(rel_path) {
+            b"" => Some((fd, b".")),
+            rel_path => Some((fd, rel_path)),
+        }
+    }
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Remove any number of leading `./`, and reduce `.` to the empty string
+fn strip_dot_slash(mut path: &[u8]) -> &[u8] {
+    loop {
+        if path == b"." {
+            return b"";
+        }
+
+        match path.strip_prefix(b"./") {
+            Some(rest) => path = trim_leading_slashes(rest),
+            None => return path,
+        }
+    }
+}
+
+/// Does the preopen `prefix` name a directory that contains `path`?
+/// The empty prefix (from `.`) contains every relative path.  Otherwise the prefix, ignoring trailing slashes, must
+/// match whole path components
+fn prefix_matches(prefix: &[u8], path: &[u8]) -> bool {
+    if prefix.is_empty() {
+        return path.first() != Some(&b'/');
+    }
+
+    if !path.starts_with(prefix) {
+        return false;
+    }
+
+    let mut i = prefix.len();
+    while i > 0 && prefix[i - 1] == b'/' {
+        i -= 1;
+    }
+
+    matches!(path.get(i), None | Some(b'/'))
+}
+
+fn trim_leading_slashes(path: &[u8]) -> &[u8] {
+    let start = path.iter().position(|&b| b != b'/').unwrap_or(path.len());
+    &path[start..]
+}
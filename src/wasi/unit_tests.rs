@@ -0,0 +1,420 @@
+use super::*;
+
+/// A preopen descriptor and the path relative to it
+type Resolved<'a> = Option<(u32, &'a [u8])>;
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_round_trip_every_errno() -> Result<(), String> {
+    for code in 0..77u16 {
+        let errno = Errno::from_raw(code);
+
+        if errno.raw() != code {
+            return Err(format!("Errno::from_raw({code}) returned {errno:?}"));
+        }
+    }
+
+    let spot_checks = [
+        (8, "badf", "Bad file descriptor"),
+        (44, "noent", "No such file or directory"),
+        (54, "notdir", "Not a directory"),
+        (63, "perm", "Operation not permitted"),
+        (76, "notcapable", "Capabilities insufficient"),
+    ];
+
+    for (code, name, message) in spot_checks {
+        let errno = Errno::from_raw(code);
+
+        if errno.name() != name || errno.to_string() != message {
+            return Err(format!(
+                "errno {code} is named {} with message {errno}",
+                errno.name()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_resolve_to_longest_preopen() -> Result<(), String> {
+    let mut preopens = Preopens::default();
+    preopens.push(Fd(3), b".");
+    preopens.push(Fd(4), b"/");
+    preopens.push(Fd(5), b"/data");
+    preopens.push(Fd(6), b"/data/archive/");
+    preopens.push(Fd(7), b"./src");
+
+    let vectors: [(&[u8], Resolved); 11] = [
+        (b"abc.txt", Some((3, b"abc.txt"))),
+        (b"./abc.txt", Some((3, b"abc.txt"))),
+        (b".", Some((3, b"."))),
+        (b"src/lib.rs", Some((7, b"lib.rs"))),
+        (b"srcs/lib.rs", Some((3, b"srcs/lib.rs"))),
+        (b"/etc/hosts", Some((4, b"etc/hosts"))),
+        (b"/data/x.bin", Some((5, b"x.bin"))),
+        (b"/data", Some((5, b"."))),
+        (b"/database", Some((4, b"database"))),
+        (b"/data/archive/2024//y.bin", Some((6, b"2024//y.bin"))),
+        (b"/data/archive", Some((5, b"archive"))),
+    ];
+
+    for (path, ans) in vectors {
+        let resolved = preopens.resolve(path).map(|(fd, rel)| (fd.0, rel));
+
+        if resolved != ans {
+            return Err(format!(
+                "resolve({:?}) returned {resolved:?}, expected {ans:?}",
+                String::from_utf8_lossy(path)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_resolve_relative_paths_against_root() -> Result<(), String> {
+    let mut preopens = Preopens::default();
+    preopens.push(Fd(3), b"/");
+
+    let resolved = preopens.resolve(b"std.rs").map(|(fd, rel)| (fd.0, rel));
+
+    if resolved != Some((3, b"std.rs".as_slice())) {
+        return Err(format!("resolve(\"std.rs\") returned {resolved:?}"));
+    }
+
+    let mut preopens = Preopens::default();
+    preopens.push(Fd(3), b"/data");
+
+    for path in [b"std.rs".as_slice(), b"/etc/hosts"] {
+        if let Some(resolved) = preopens.resolve(path) {
+            return Err(format!("resolve({path:?}) returned {resolved:?}"));
+        }
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_iterate_any_number_of_args() -> Result<(), String> {
+    let names: Vec<Vec<u8>> = (0..300)
+        .map(|i| format!("file-{i}.bin").into_bytes())
+        .collect();
+    let mut buf = Vec::new();
+    let mut ranges = Vec::new();
+
+    for name in &names {
+        ranges.push(buf.len()..buf.len() + name.len());
+        buf.extend_from_slice(name);
+        buf.push(0);
+    }
+
+    let args = Args::new(buf, ranges);
+
+    if args.len() != names.len() || !args.iter().eq(names.iter().map(Vec::as_slice)) {
+        return Err(format!(
+            "Args held {} arguments, expected {}",
+            args.len(),
+            names.len()
+        ));
+    }
+
+    if args.get(299) != Some(b"file-299.bin".as_slice()) || args.get(300).is_some() {
+        return Err("Args::get() returned the wrong argument".to_string());
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Append a `__wasi_dirent_t` header and name, as `fd_readdir` writes them
+fn push_dirent(buf: &mut Vec<u8>, d_next: u64, name: &[u8], d_type: u8) {
+    buf.extend_from_slice(&d_next.to_le_bytes());
+    buf.extend_from_slice(&0u64.to_le_bytes()); // d_ino
+    buf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&[d_type, 0, 0, 0]); // d_type and padding
+    buf.extend_from_slice(name);
+}
+
+#[test]
+fn should_parse_complete_dirents_only() -> Result<(), String> {
+    let mut buf = Vec::new();
+    push_dirent(&mut buf, 1, b".", 3);
+    push_dirent(&mut buf, 2, b"..", 3);
+    push_dirent(&mut buf, 3, b"src", 3);
+    push_dirent(&mut buf, 4, b"README.md", 4);
+    let complete_len = buf.len();
+    push_dirent(&mut buf, 5, b"Cargo.toml", 4);
+
+    let ans = [
+        DirEntry {
+            name: b"src".to_vec(),
+            filetype: Filetype::Directory,
+        },
+        DirEntry {
+            name: b"README.md".to_vec(),
+            filetype: Filetype::RegularFile,
+        },
+    ];
+
+    // The last entry is cut short within its name, then within its header
+    for len in [buf.len() - 1, complete_len + 10] {
+        let mut entries = Vec::new();
+        let cookie = walk::parse_dirents(&buf[..len], &mut entries);
+
+        if cookie != Some(4) || entries != ans {
+            return Err(format!(
+                "parse_dirents() of {len} bytes returned cookie {cookie:?} and {entries:?}"
+            ));
+        }
+    }
+
+    let mut entries = Vec::new();
+    let cookie = walk::parse_dirents(&buf[..20], &mut entries);
+
+    if cookie.is_some() || !entries.is_empty() {
+        return Err(format!(
+            "parse_dirents() of a partial header returned {cookie:?}"
+        ));
+    }
+
+    Ok(())
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// The tests below run the wrappers against `fake::FakeHost`
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// Read the whole of an open file, one `wasi_fd_read` at a time
+fn read_all(fd: Fd) -> Result<Vec<u8>, Errno> {
+    let mut contents = Vec::new();
+    let mut buf = [0u8; 4096];
+
+    loop {
+        match wasi_fd_read(fd, &mut buf)? {
+            0 => return Ok(contents),
+            n => contents.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[test]
+fn should_get_oversized_args() -> Result<(), String> {
+    let args: Vec<Vec<u8>> = (0..1000)
+        .map(|i| format!("{i:0>200}").into_bytes())
+        .collect();
+
+    args.iter()
+        .fold(fake::FakeHost::new(), |host, arg| host.arg(arg))
+        .install();
+
+    let got = wasi_args_get().map_err(|e| format!("wasi_args_get() failed: {e}"))?;
+
+    if got.len() != args.len() || !got.iter().eq(args.iter().map(Vec::as_slice)) {
+        return Err(format!(
+            "wasi_args_get() returned {} arguments, expected {}",
+            got.len(),
+            args.len()
+        ));
+    }
+
+    fake::FakeHost::new()
+        .arg(b"sha256")
+        .fail("args_get", Errno::Fault)
+        .install();
+
+    match wasi_args_get() {
+        Err(Errno::Fault) => Ok(()),
+        other => Err(format!(
+            "a failed args_get returned {:?}",
+            other.map(|args| args.len())
+        )),
+    }
+}
+
+#[test]
+fn should_discover_preopens() -> Result<(), String> {
+    fake::FakeHost::new()
+        .preopen(b".", b"work")
+        .preopen(b"/data", b"data")
+        .install();
+
+    let preopens = Preopens::discover().map_err(|e| format!("discover() failed: {e}"))?;
+    let names: Vec<(u32, &[u8])> = preopens
+        .iter()
+        .map(|dir| (dir.fd.0, dir.name.as_slice()))
+        .collect();
+
+    if names != [(3, b"".as_slice()), (4, b"/data")] {
+        return Err(format!("discover() found {names:?}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_read_whole_file_despite_short_reads() -> Result<(), String> {
+    let contents: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+
+    fake::FakeHost::new()
+        .preopen(b".", b"")
+        .file(b"big.bin", &contents)
+        .short_reads(7)
+        .install();
+
+    let file = wasi_path_open(Fd(3), b"big.bin").map_err(|e| format!("path_open failed: {e}"))?;
+    let stat =
+        wasi_fd_filestat_get(file.fd()).map_err(|e| format!("fd_filestat_get failed: {e}"))?;
+    let read = read_all(file.fd()).map_err(|e| format!("fd_read failed: {e}"))?;
+
+    if stat.filetype != Filetype::RegularFile || stat.size != contents.len() as u64 {
+        return Err(format!("fd_filestat_get() returned {stat:?}"));
+    }
+
+    if read != contents {
+        return Err(format!(
+            "read {} bytes, expected {}",
+            read.len(),
+            contents.len()
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_report_interrupted_read() -> Result<(), String> {
+    fake::FakeHost::new()
+        .preopen(b".", b"")
+        .file(b"abc.txt", b"abc")
+        .fail("fd_read", Errno::Intr)
+        .install();
+
+    let file = wasi_path_open(Fd(3), b"abc.txt").map_err(|e| format!("path_open failed: {e}"))?;
+    let mut buf = [0u8; 16];
+
+    if let Ok(n) = wasi_fd_read(file.fd(), &mut buf) {
+        return Err(format!("the interrupted read returned {n} bytes"));
+    }
+
+    // Only the one call was interrupted, and nothing was consumed
+    match read_all(file.fd()) {
+        Ok(read) if read == b"abc" => Ok(()),
+        other => Err(format!(
+            "reading after the interrupted read returned {other:?}"
+        )),
+    }
+}
+
+#[test]
+fn should_write_everything_despite_short_writes() -> Result<(), String> {
+    fake::FakeHost::new()
+        .short_writes(3)
+        .fail("fd_write", Errno::Intr)
+        .install();
+
+    // More buffers than fit in one call, some of them empty
+    let bufs: Vec<Vec<u8>> = (0..20u8)
+        .map(|i| vec![b'a' + i; (i % 4) as usize])
+        .collect();
+    let buf_refs: Vec<&[u8]> = bufs.iter().map(Vec::as_slice).collect();
+    let expected = bufs.concat();
+
+    let written =
+        wasi_fd_write(Fd::STDOUT, &buf_refs).map_err(|e| format!("fd_write failed: {e}"))?;
+    let stdout = fake::take_stdout();
+
+    if written != expected.len() || stdout != expected {
+        return Err(format!(
+            "wrote {written} bytes {:?}, expected {:?}",
+            String::from_utf8_lossy(&stdout),
+            String::from_utf8_lossy(&expected)
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_report_missing_files() -> Result<(), String> {
+    fake::FakeHost::new()
+        .preopen(b".", b"")
+        .file(b"dir/file.txt", b"")
+        .install();
+
+    let vectors: [(&[u8], bool, Errno); 4] = [
+        (b"missing.txt", false, Errno::NoEnt),
+        (b"dir/missing.txt", false, Errno::NoEnt),
+        (b"../escape.txt", false, Errno::NotCapable),
+        (b"dir/file.txt", true, Errno::NotDir),
+    ];
+
+    for (path, as_dir, ans) in vectors {
+        let result = if as_dir {
+            wasi_path_open_dir(Fd(3), path)
+        } else {
+            wasi_path_open(Fd(3), path)
+        };
+
+        if result.as_ref().err() != Some(&ans) {
+            return Err(format!(
+                "opening {:?} returned {result:?}, expected {ans:?}",
+                String::from_utf8_lossy(path)
+            ));
+        }
+    }
+
+    match wasi_path_filestat_get(Fd(3), b"missing.txt") {
+        Err(Errno::NoEnt) => Ok(()),
+        other => Err(format!(
+            "path_filestat_get of a missing file returned {other:?}"
+        )),
+    }
+}
+
+#[test]
+fn should_walk_directories_in_sorted_order() -> Result<(), String> {
+    // Enough long names that a directory takes several `fd_readdir` calls to list
+    let names: Vec<String> = (0..200)
+        .map(|i| format!("many/{i:03}-{}.bin", "x".repeat(40)))
+        .collect();
+
+    let host = fake::FakeHost::new()
+        .preopen(b".", b"")
+        .file(b"tree/b.txt", b"b")
+        .file(b"tree/a/z.txt", b"z")
+        .file(b"tree/a.txt", b"a")
+        .dir(b"tree/empty");
+    let host = names.iter().fold(host, |host, name| {
+        host.file(format!("tree/{name}").as_bytes(), b"")
+    });
+    host.install();
+
+    let dir =
+        wasi_path_open_dir(Fd(3), b"tree").map_err(|e| format!("opening tree failed: {e}"))?;
+    let walk = Walk::new(dir, b"tree").map_err(|e| format!("listing tree failed: {e}"))?;
+    let paths = walk
+        .map(|entry| entry.map(|entry| String::from_utf8_lossy(&entry.path).into_owned()))
+        .collect::<Result<Vec<String>, WalkError>>()
+        .map_err(|e| format!("walk failed: {e:?}"))?;
+
+    let mut ans = vec![
+        "tree/a/z.txt".to_string(),
+        "tree/a.txt".to_string(),
+        "tree/b.txt".to_string(),
+    ];
+    ans.extend(names.iter().map(|name| format!("tree/{name}")));
+
+    if paths != ans {
+        return Err(format!(
+            "walk returned {} paths, starting {:?}",
+            paths.len(),
+            &paths[..3]
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use super::{
+    Errno, Fd, Filetype, OwnedFd, wasi_fd_readdir, wasi_path_filestat_get, wasi_path_open_dir,
+};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Directory traversal
+//
+// `fd_readdir` fills a buffer with as many `__wasi_dirent_t` headers, each followed by its name, as will fit, and the
+// last one may be cut short.  Each header holds the cookie to pass to `fd_readdir` to continue after that entry, so a
+// listing is read by resuming from the last complete entry until the host returns less than a full buffer.
+//
+// Hosts list a directory in no particular order, so each listing is sorted by name to make the output reproducible
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+const DIRENT_SIZE: usize = size_of::<super::interface::Dirent>();
+const READDIR_BUF_SIZE: usize = 4096;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: Vec<u8>,
+    /// The type from the listing, which some hosts report as `Unknown`
+    pub filetype: Filetype,
+}
+
+/// List a directory, sorted by name and without the `.` and `..` entries
+pub fn wasi_read_dir(fd: Fd) -> Result<Vec<DirEntry>, Errno> {
+    let mut entries = Vec::new();
+    let mut buf = vec![0u8; READDIR_BUF_SIZE];
+    let mut cookie = 0;
+
+    loop {
+        let bufused = wasi_fd_readdir(fd, &mut buf, cookie)?;
+        let next_cookie = parse_dirents(&buf[..bufused], &mut entries);
+
+        if bufused < buf.len() {
+            break;
+        }
+
+        match next_cookie {
+            Some(next_cookie) => cookie = next_cookie,
+
+            // Not even one entry fitted, so make room for a longer name
+            None => buf.resize(buf.len() * 2, 0),
+        }
+    }
+
+    entries.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Append every complete entry in `buf`, other than `.` and `..`, to `entries`.
+/// Returns the cookie following the last complete entry, or `None` if there was no complete entry
+pub(super) fn parse_dirents(mut buf: &[u8], entries: &mut Vec<DirEntry>) -> Option<u64> {
+    let mut next_cookie = None;
+
+    while buf.len() >= DIRENT_SIZE {
+        let (header, rest) = buf.split_at(DIRENT_SIZE);
+        let d_next = u64::from_le_bytes(header[0..8].try_into().unwrap());
+        let d_namlen = u32::from_le_bytes(header[16..20].try_into().unwrap()) as usize;
+        let d_type = header[20];
+
+        let Some(name) = rest.get(..d_namlen) else {
+            break;
+        };
+
+        if name != b"." && name != b".." {
+            entries.push(DirEntry {
+                name: name.to_vec(),
+                filetype: Filetype::from_raw(d_type),
+            });
+        }
+
+        next_cookie = Some(d_next);
+        buf = &rest[d_namlen..];
+    }
+
+    next_cookie
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// A regular file found by `Walk`
+pub struct WalkEntry {
+    /// The path of the directory given to `Walk::new`, followed by the path to the file within it
+    pub path: Vec<u8>,
+    /// The directory containing the file, which stays open until the walk moves on to the next entry
+    pub dir_fd: Fd,
+    name_start: usize,
+}
+
+impl WalkEntry {
+    /// The file name, relative to `dir_fd`
+    pub fn name(&self) -> &[u8] {
+        &self.path[self.name_start..]
+    }
+}
+
+/// A subdirectory that could not be examined, opened or listed
+#[derive(Debug)]
+pub struct WalkError {
+    pub path: Vec<u8>,
+    pub errno: Errno,
+}
+
+/// An open directory and the entries in it that have not yet been visited
+struct Level {
+    dir: OwnedFd,
+    path: Vec<u8>,
+    /// Sorted in reverse, so that the next entry can be popped off the end
+    pending: Vec<DirEntry>,
+}
+
+impl Level {
+    fn new(dir: OwnedFd, path: Vec<u8>) -> Result<Level, Errno> {
+        let mut pending = wasi_read_dir(dir.fd())?;
+        pending.reverse();
+
+        Ok(Level { dir, path, pending })
+    }
+}
+
+/// Every regular file beneath a directory, depth first and in sorted order.
+/// The walk uses an explicit stack of open directories rather than recursion.  Symlinks are not followed, and anything
+/// that is neither a regular file nor a directory is skipped
+pub struct Walk {
+    stack: Vec<Level>,
+}
+
+impl Walk {
+    /// Begin a walk of the open directory `dir`, whose path is `path`
+    pub fn new(dir: OwnedFd, path: &[u8]) -> Result<Walk, Errno> {
+        Ok(Walk {
+            stack: vec![Level::new(dir, path.to_vec())?],
+        })
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<WalkEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let level = self.stack.last_mut()?;
+
+            let Some(entry) = level.pending.pop() else {
+                self.stack.pop();
+                continue;
+            };
+
+            let dir_fd = level.dir.fd();
+            let path = join(&level.path, &entry.name);
+
+            let filetype = match entry.filetype {
+                Filetype::Unknown => match wasi_path_filestat_get(dir_fd, &entry.name) {
+                    Ok(stat) => stat.filetype,
+                    Err(errno) => return Some(Err(WalkError { path, errno })),
+                },
+                filetype => filetype,
+            };
+
+            match filetype {
+                Filetype::RegularFile => {
+                    let name_start = path.len() - entry.name.len();
+                    return Some(Ok(WalkEntry {
+                        path,
+                        dir_fd,
+                        name_start,
+                    }));
+                }
+                Filetype::Directory => {
+                    match wasi_path_open_dir(dir_fd, &entry.name)
+                        .and_then(|dir| Level::new(dir, path.clone()))
+                    {
+                        Ok(level) => self.stack.push(level),
+                        Err(errno) => return Some(Err(WalkError { path, errno })),
+                    }
+                }
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// Append `name` to a directory path, adding a separator if there isn't one already
+fn join(dir_path: &[u8], name: &[u8]) -> Vec<u8> {
+    let mut path = Vec::with_capacity(dir_path.len() + 1 + name.len());
+    path.extend_from_slice(dir_path);
+
+    if !path.is_empty() && path.last() != Some(&b'/') {
+        path.push(b'/');
+    }
+
+    path.extend_from_slice(name);
+    path
+}
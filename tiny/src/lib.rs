@@ -0,0 +1,140 @@
+#![cfg_attr(target_arch = "wasm32", no_std)]
+
+use sha256::{
+    sha256::{Digest, Sha256},
+    text::{HEX_CHARS, NumBuf, hex_encode_into},
+    wasi::{
+        Errno, Fd, arg_range, wasi_args_get_into, wasi_fd_read, wasi_fd_write, wasi_path_open,
+        wasi_preopened_dirs, wasi_proc_exit,
+    },
+};
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Allocator-free WASI command
+//
+// Built for `wasm32-unknown-unknown` on the sha256 crate's `no_std` WASI wrappers, this module links neither the
+// standard library nor wasi-libc, so it has no allocator: the arguments and the read buffer are stack arrays.
+// It exports `_start` and its memory, which is all a WASI host needs to run it as a command.
+//
+// Each file named on the command line is opened relative to the first preopened directory, and `<digest>  <path>` is
+// written for each one.  A file that cannot be hashed is reported as `sha256: <path>: errno <code>`
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+const MAX_ARGS: usize = 64;
+const ARG_BUF_SIZE: usize = 4096;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+static MSG_USAGE: &[u8] = b"Usage: sha256 <filename>...\n";
+static ERR_PREFIX: &[u8] = b"sha256: ";
+static ERR_ARGS: &[u8] = b"at most 64 arguments totalling 4096 bytes are supported\n";
+static ERR_READ_ARGS: &[u8] = b"unable to read command line arguments";
+static ERR_ERRNO: &[u8] = b": errno ";
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// The command's entry point.  Exits with status 1 if any file could not be hashed
+#[cfg_attr(target_arch = "wasm32", unsafe(no_mangle))]
+pub extern "C" fn _start() {
+    let status = if run() { 0 } else { 1 };
+
+    wasi_proc_exit(status)
+}
+
+fn run() -> bool {
+    let mut argv = [core::ptr::null_mut::<u8>(); MAX_ARGS];
+    let mut argv_buf = [0u8; ARG_BUF_SIZE];
+
+    let argc = match wasi_args_get_into(&mut argv, &mut argv_buf) {
+        Ok(argc) => argc,
+        Err(Errno::TooBig) => {
+            let _ = wasi_fd_write(Fd::STDERR, &[ERR_PREFIX, ERR_ARGS]);
+            return false;
+        }
+        Err(errno) => {
+            write_error(ERR_READ_ARGS, errno);
+            return false;
+        }
+    };
+
+    if argc < 2 {
+        let _ = wasi_fd_write(Fd::STDERR, &[MSG_USAGE]);
+        return false;
+    }
+
+    let dir = first_preopen();
+    let mut buffer = [0u8; CHUNK_SIZE];
+    let mut all_ok = true;
+
+    for &arg in &argv[1..argc] {
+        let Some(range) = arg_range(&argv_buf, arg) else {
+            write_error(ERR_READ_ARGS, Errno::Fault);
+            return false;
+        };
+        let path = &argv_buf[range];
+
+        match hash_file(dir, path, &mut buffer) {
+            Ok(digest) => {
+                let mut hex = [0u8; 64];
+                hex_encode_into(digest.as_bytes(), HEX_CHARS, &mut hex);
+                let _ = wasi_fd_write(Fd::STDOUT, &[&hex, b"  ", path, b"\n"]);
+            }
+            Err(errno) => {
+                write_error(path, errno);
+                all_ok = false;
+            }
+        }
+    }
+
+    all_ok
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/// The directory every path is opened relative to.  Without a preopened directory, no file can be opened
+fn first_preopen() -> Result<Fd, Errno> {
+    match wasi_preopened_dirs().next() {
+        Some(preopen) => preopen.map(|(fd, _)| fd),
+        None => Err(Errno::NotCapable),
+    }
+}
+
+/// Hash a file relative to the preopened directory
+fn hash_file(dir: Result<Fd, Errno>, path: &[u8], buffer: &mut [u8]) -> Result<Digest, Errno> {
+    let file = wasi_path_open(dir?, relative_path(path))?;
+    let mut hasher = Sha256::new();
+
+    loop {
+        match wasi_fd_read(file.fd(), buffer) {
+            Ok(0) => return Ok(hasher.finalize()), // EOF
+            Ok(bytes_read) => hasher.update(&buffer[..bytes_read]),
+            Err(Errno::Intr) => continue,
+            Err(errno) => return Err(errno),
+        }
+    }
+}
+
+/// Strip any leading `/` and `./`, since every path is opened relative to the preopened directory
+fn relative_path(mut path: &[u8]) -> &[u8] {
+    loop {
+        match path {
+            [b'/', rest @ ..] | [b'.', b'/', rest @ ..] => path = rest,
+            [] => return b".",
+            _ => return path,
+        }
+    }
+}
+
+/// Write `sha256: <context>: errno <code>`.  There is nowhere to report a failure to write
+fn write_error(context: &[u8], errno: Errno) {
+    let code = NumBuf::decimal(errno.raw() as u64);
+    let _ = wasi_fd_write(
+        Fd::STDERR,
+        &[ERR_PREFIX, context, ERR_ERRNO, code.as_bytes(), b"\n"],
+    );
+}
+
+#[cfg(test)]
+mod unit_tests;
@@ -0,0 +1,82 @@
+use super::*;
+use sha256::wasi::fake::{self, FakeHost};
+
+static ABC_DIGEST: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+static EMPTY_DIGEST: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Run the command, returning whether it succeeded with what it wrote to stdout and stderr
+fn run_command() -> (bool, String, String) {
+    let ok = run();
+    let stdout = String::from_utf8_lossy(&fake::take_stdout()).into_owned();
+    let stderr = String::from_utf8_lossy(&fake::take_stderr()).into_owned();
+
+    (ok, stdout, stderr)
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[test]
+fn should_hash_files_in_first_preopen() -> Result<(), String> {
+    FakeHost::new()
+        .arg(b"sha256")
+        .arg(b"abc.txt")
+        .arg(b"/sub/empty.txt")
+        .arg(b"missing.txt")
+        .preopen(b"/data", b"data")
+        .preopen(b"/other", b"other")
+        .file(b"data/abc.txt", b"abc")
+        .file(b"data/sub/empty.txt", b"")
+        .file(b"other/missing.txt", b"not in the first preopen")
+        .install();
+
+    let got = run_command();
+    let expected = (
+        false,
+        format!("{ABC_DIGEST}  abc.txt\n{EMPTY_DIGEST}  /sub/empty.txt\n"),
+        "sha256: missing.txt: errno 44\n".to_string(),
+    );
+
+    if got != expected {
+        return Err(format!("got {got:?}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_report_files_without_preopen() -> Result<(), String> {
+    FakeHost::new().arg(b"sha256").arg(b"abc.txt").install();
+
+    let got = run_command();
+
+    if got
+        != (
+            false,
+            String::new(),
+            "sha256: abc.txt: errno 76\n".to_string(),
+        )
+    {
+        return Err(format!("got {got:?}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_refuse_too_many_args() -> Result<(), String> {
+    (0..=MAX_ARGS)
+        .fold(FakeHost::new(), |host, _| host.arg(b"abc.txt"))
+        .install();
+
+    let got = run_command();
+    let expected = (
+        false,
+        String::new(),
+        "sha256: at most 64 arguments totalling 4096 bytes are supported\n".to_string(),
+    );
+
+    if got != expected {
+        return Err(format!("got {got:?}"));
+    }
+
+    Ok(())
+}